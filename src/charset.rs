@@ -0,0 +1,193 @@
+use crate::FromProblemInputLine;
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitOr, BitXor, Sub};
+
+/// A set of ASCII letters and digits, stored as a 64 bit mask.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CharSet(u64);
+
+/// The bit representing `c`: `a-z` use bits 0 to 25, `A-Z` bits 26 to 51 and
+/// `0-9` bits 52 to 61.
+fn bit(c: char) -> Option<u32> {
+    match c {
+        'a'..='z' => Some(c as u32 - 'a' as u32),
+        'A'..='Z' => Some(c as u32 - 'A' as u32 + 26),
+        '0'..='9' => Some(c as u32 - '0' as u32 + 52),
+        _ => None,
+    }
+}
+
+fn char_for(bit: u32) -> char {
+    let (base, offset) = match bit {
+        0..=25 => ('a', 0),
+        26..=51 => ('A', 26),
+        _ => ('0', 52),
+    };
+
+    (base as u8 + (bit - offset) as u8) as char
+}
+
+impl CharSet {
+    pub fn new() -> Self {
+        Self(0)
+    }
+
+    /// Every character which can be stored in a `CharSet`.
+    pub fn all() -> Self {
+        Self((1 << 62) - 1)
+    }
+
+    /// Adds `c` to the set, returning whether it was newly added.
+    ///
+    /// # Panics
+    /// If `c` is not an ASCII letter or digit.
+    pub fn insert(&mut self, c: char) -> bool {
+        let mask = 1 << bit(c).unwrap_or_else(|| panic!("{:?} cannot be stored in a CharSet", c));
+        let added = self.0 & mask == 0;
+        self.0 |= mask;
+
+        added
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        matches!(bit(c), Some(b) if self.0 & (1 << b) != 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    pub fn symmetric_difference(self, other: Self) -> Self {
+        Self(self.0 ^ other.0)
+    }
+
+    /// The characters in the set, in the order `a-z`, `A-Z`, `0-9`.
+    pub fn iter(self) -> impl Iterator<Item = char> {
+        (0..62)
+            .filter(move |b| self.0 & (1 << b) != 0)
+            .map(char_for)
+    }
+
+    /// The characters found in at least `k` of `sets`.
+    pub fn at_least(sets: &[CharSet], k: usize) -> Self {
+        let mut result = Self::new();
+
+        for b in 0..62 {
+            if sets.iter().filter(|s| s.0 & (1 << b) != 0).count() >= k {
+                result.0 |= 1 << b;
+            }
+        }
+
+        result
+    }
+
+    /// The characters found in any of `sets`.
+    pub fn union_all(sets: &[CharSet]) -> Self {
+        sets.iter().fold(Self::new(), |x, &y| x | y)
+    }
+
+    /// The characters found in every one of `sets`; empty if there are no sets.
+    pub fn intersection_all(sets: &[CharSet]) -> Self {
+        if sets.is_empty() {
+            return Self::new();
+        }
+
+        sets.iter().fold(Self::all(), |x, &y| x & y)
+    }
+}
+
+impl BitOr for CharSet {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        self.union(other)
+    }
+}
+
+impl BitAnd for CharSet {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        self.intersection(other)
+    }
+}
+
+impl Sub for CharSet {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.difference(other)
+    }
+}
+
+impl BitXor for CharSet {
+    type Output = Self;
+
+    fn bitxor(self, other: Self) -> Self {
+        self.symmetric_difference(other)
+    }
+}
+
+impl FromIterator<char> for CharSet {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for c in iter {
+            set.insert(c);
+        }
+
+        set
+    }
+}
+
+impl FromProblemInputLine for CharSet {
+    fn from_line(line: &str) -> Self {
+        line.chars().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProblemInput;
+
+    #[test]
+    fn test_group_statistics() {
+        let group: Vec<CharSet> = ProblemInput::from(vec!["abc", "abd", "ae", "a"]).parse();
+
+        assert_eq!(CharSet::at_least(&group, 1), CharSet::union_all(&group));
+        assert_eq!(
+            CharSet::at_least(&group, 2).iter().collect::<String>(),
+            "ab"
+        );
+        assert_eq!(
+            CharSet::at_least(&group, 4),
+            CharSet::intersection_all(&group)
+        );
+        assert_eq!((group[0] - group[1]).iter().collect::<String>(), "c");
+        assert_eq!((group[0] ^ group[1]).iter().collect::<String>(), "cd");
+        assert_eq!(
+            "zA9"
+                .chars()
+                .collect::<CharSet>()
+                .iter()
+                .collect::<String>(),
+            "zA9"
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use crate::{FromProblemInput, ProblemInput};
+use anyhow::{anyhow, Context, Result};
+use num::traits::{CheckedAdd, CheckedMul};
+use num::{FromPrimitive, One, Zero};
+use petgraph::algo::toposort;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::{depth_first_search, Control, Dfs, DfsEvent, EdgeRef, Reversed};
+use petgraph::Graph;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::str::FromStr;
+
+/// A graph of rules of the form "X contains N Y", with an edge of weight N from X to Y.
+#[derive(Debug, Clone)]
+pub struct ContainmentGraph<N> {
+    pub graph: Graph<N, usize>,
+    index: HashMap<N, NodeIndex>,
+}
+
+impl<N: Clone + Eq + Hash + Display> Default for ContainmentGraph<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: Clone + Eq + Hash + Display> ContainmentGraph<N> {
+    pub fn new() -> Self {
+        Self {
+            graph: Graph::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    /// Adds `node` to the graph if it isn't already present, returning its index.
+    pub fn add_node(&mut self, node: N) -> NodeIndex {
+        let graph = &mut self.graph;
+        *self
+            .index
+            .entry(node.clone())
+            .or_insert_with(|| graph.add_node(node))
+    }
+
+    /// Records that `container` directly contains each of `contents`.
+    pub fn add_rule(&mut self, container: N, contents: Vec<(usize, N)>) {
+        let source = self.add_node(container);
+
+        for (count, node) in contents {
+            let target = self.add_node(node);
+            self.graph.add_edge(source, target, count);
+        }
+    }
+
+    pub fn node(&self, node: &N) -> Result<NodeIndex> {
+        self.index
+            .get(node)
+            .copied()
+            .ok_or_else(|| anyhow!("no rule mentions {}", node))
+    }
+
+    /// Everything which (directly or indirectly) contains `node`.
+    pub fn ancestors(&self, node: &N) -> Result<Vec<&N>> {
+        let start = self.node(node)?;
+        let reversed = Reversed(&self.graph);
+
+        let mut dfs = Dfs::new(reversed, start);
+        let mut ancestors = Vec::new();
+        while let Some(n) = dfs.next(reversed) {
+            if n != start {
+                ancestors.push(&self.graph[n]);
+            }
+        }
+
+        Ok(ancestors)
+    }
+
+    /// Everything which `node` (directly or indirectly) contains.
+    pub fn descendants(&self, node: &N) -> Result<Vec<&N>> {
+        let start = self.node(node)?;
+
+        let mut dfs = Dfs::new(&self.graph, start);
+        let mut descendants = Vec::new();
+        while let Some(n) = dfs.next(&self.graph) {
+            if n != start {
+                descendants.push(&self.graph[n]);
+            }
+        }
+
+        Ok(descendants)
+    }
+
+    /// Returns an error naming a node on a cycle, if the rules contain one.
+    pub fn check_acyclic(&self) -> Result<()> {
+        toposort(&self.graph, None).map(|_| ()).map_err(|cycle| {
+            anyhow!(
+                "containment rules are cyclic: {} contains itself",
+                self.graph[cycle.node_id()]
+            )
+        })
+    }
+
+    /// The total number of items inside `node`, counting nested contents.
+    ///
+    /// The count is computed in `T`, failing if it overflows; use a big integer type
+    /// such as `num::BigUint` for counts which cannot fit in a machine word.  It also
+    /// fails if `node` can reach a cycle, since its contents would then be infinite.
+    pub fn total_contents<T>(&self, node: &N) -> Result<T>
+    where
+        T: Clone + Zero + One + CheckedAdd + CheckedMul + FromPrimitive,
+    {
+        let start = self.node(node)?;
+        let mut totals: HashMap<NodeIndex, T> = HashMap::new();
+
+        // Nodes finish after everything inside them, and a back edge means the rules
+        // reachable from `start` are cyclic; cycles elsewhere in the graph don't matter.
+        let control = depth_first_search(&self.graph, Some(start), |event| match event {
+            DfsEvent::BackEdge(_, v) => Control::Break(anyhow!(
+                "containment rules are cyclic: {} contains itself",
+                self.graph[v]
+            )),
+            DfsEvent::Finish(n, _) => match self.contents_of(n, &totals) {
+                Ok(total) => {
+                    totals.insert(n, total);
+                    Control::Continue
+                }
+                Err(e) => Control::Break(e),
+            },
+            _ => Control::Continue,
+        });
+
+        match control.break_value() {
+            Some(e) => Err(e),
+            None => Ok(totals.remove(&start).unwrap()),
+        }
+    }
+
+    /// The number of items inside `n`, given the totals for everything directly in it.
+    fn contents_of<T>(&self, n: NodeIndex, totals: &HashMap<NodeIndex, T>) -> Result<T>
+    where
+        T: Clone + Zero + One + CheckedAdd + CheckedMul + FromPrimitive,
+    {
+        let overflow = || anyhow!("overflow counting contents of {}", self.graph[n]);
+        let mut total = T::zero();
+
+        for edge in self.graph.edges(n) {
+            let count = T::from_usize(*edge.weight()).ok_or_else(overflow)?;
+            let inner = T::one()
+                .checked_add(&totals[&edge.target()])
+                .and_then(|inner| count.checked_mul(&inner))
+                .ok_or_else(overflow)?;
+
+            total = total.checked_add(&inner).ok_or_else(overflow)?;
+        }
+
+        Ok(total)
+    }
+}
+
+impl<N> ContainmentGraph<N>
+where
+    N: Clone + Eq + Hash + Display + FromStr,
+    N::Err: Into<anyhow::Error>,
+{
+    /// Parses rules such as `light red bags contain 1 bright white bag, 2 muted yellow bags.`
+    pub fn parse_rules<S: AsRef<str>>(lines: &[S]) -> Result<Self> {
+        let rule = Regex::new(r"^(?P<container>.+?) bags? contain (?P<contents>.+)\.$").unwrap();
+        let item = Regex::new(r"^(?P<count>\d+) (?P<name>.+?) bags?$").unwrap();
+
+        let mut graph = Self::new();
+
+        for line in lines {
+            let line = line.as_ref();
+            let parse_node = |s: &str| -> Result<N> {
+                s.parse()
+                    .map_err(Into::into)
+                    .with_context(|| format!("invalid rule {}", line))
+            };
+
+            let caps = rule
+                .captures(line)
+                .ok_or_else(|| anyhow!("invalid rule {}", line))?;
+
+            let container = parse_node(&caps["container"])?;
+
+            let mut contents = Vec::new();
+            if &caps["contents"] != "no other bags" {
+                for part in caps["contents"].split(", ") {
+                    let caps = item
+                        .captures(part)
+                        .ok_or_else(|| anyhow!("invalid contents {} in rule {}", part, line))?;
+
+                    contents.push((caps["count"].parse()?, parse_node(&caps["name"])?));
+                }
+            }
+
+            graph.add_rule(container, contents);
+        }
+
+        Ok(graph)
+    }
+}
+
+impl<N> FromProblemInput for ContainmentGraph<N>
+where
+    N: Clone + Eq + Hash + Display + FromStr,
+    N::Err: Into<anyhow::Error>,
+{
+    fn from(lines: &ProblemInput) -> Self {
+        Self::parse_rules(&lines.lines).expect("failed to parse rules")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num::BigUint;
+
+    #[test]
+    fn test_cyclic_rules() {
+        let shiny_gold = "shiny gold".to_string();
+        let g = ContainmentGraph::<String>::parse_rules(&[
+            "shiny gold bags contain 2 dark red bags.",
+            "dark red bags contain 1 shiny gold bag, 3 faded blue bags.",
+            "faded blue bags contain no other bags.",
+        ])
+        .unwrap();
+
+        assert!(g.check_acyclic().is_err());
+        assert!(g.total_contents::<u64>(&shiny_gold).is_err());
+        assert_eq!(g.descendants(&shiny_gold).unwrap().len(), 2);
+
+        // A cycle which can't be reached from the bag being counted doesn't matter.
+        let g = ContainmentGraph::<String>::parse_rules(&[
+            "shiny gold bags contain 2 faded blue bags.",
+            "faded blue bags contain no other bags.",
+            "dark red bags contain 1 dotted black bag.",
+            "dotted black bags contain 1 dark red bag.",
+        ])
+        .unwrap();
+
+        assert!(g.check_acyclic().is_err());
+        assert_eq!(g.total_contents::<u64>(&shiny_gold).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_contents_overflow() {
+        let shiny_gold = "shiny gold".to_string();
+        let g = ContainmentGraph::<String>::parse_rules(&[
+            "shiny gold bags contain 65536 dark red bags.",
+            "dark red bags contain 65536 faded blue bags.",
+            "faded blue bags contain 65536 dotted black bags.",
+            "dotted black bags contain no other bags.",
+        ])
+        .unwrap();
+
+        assert!(g.total_contents::<u32>(&shiny_gold).is_err());
+        assert_eq!(
+            g.total_contents::<BigUint>(&shiny_gold).unwrap(),
+            BigUint::from(281_479_271_743_488_u64)
+        );
+
+        // A single count too large for the type is an error rather than a panic.
+        let g = ContainmentGraph::<String>::parse_rules(&[
+            "shiny gold bags contain 300 dark red bags.",
+            "dark red bags contain no other bags.",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            g.total_contents::<u8>(&shiny_gold).unwrap_err().to_string(),
+            "overflow counting contents of shiny gold"
+        );
+    }
+}
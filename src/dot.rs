@@ -0,0 +1,121 @@
+use petgraph::graph::NodeIndex;
+use petgraph::visit::{Dfs, EdgeRef};
+use petgraph::Graph;
+use std::fmt::{Display, Write};
+
+fn escape<T: Display>(t: &T) -> String {
+    t.to_string().replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Renders `graph` in Graphviz DOT format, labelling nodes and edges by their weights.
+///
+/// If `highlight` is given, every node reachable from it is drawn inside a shaded
+/// cluster so the relevant part of a large graph stands out.
+pub fn to_dot<N: Display, E: Display>(graph: &Graph<N, E>, highlight: Option<NodeIndex>) -> String {
+    let mut reachable = vec![false; graph.node_count()];
+    if let Some(start) = highlight {
+        let mut dfs = Dfs::new(graph, start);
+        while let Some(n) = dfs.next(graph) {
+            reachable[n.index()] = true;
+        }
+    }
+
+    let mut out = String::new();
+    writeln!(out, "digraph {{").unwrap();
+
+    for n in graph.node_indices().filter(|n| !reachable[n.index()]) {
+        writeln!(out, "    {} [label=\"{}\"];", n.index(), escape(&graph[n])).unwrap();
+    }
+
+    if let Some(start) = highlight {
+        writeln!(out, "    subgraph cluster_reachable {{").unwrap();
+        writeln!(
+            out,
+            "        label=\"reachable from {}\";",
+            escape(&graph[start])
+        )
+        .unwrap();
+        writeln!(out, "        style=filled;").unwrap();
+        writeln!(out, "        color=lightgrey;").unwrap();
+
+        for n in graph.node_indices().filter(|n| reachable[n.index()]) {
+            let shape = if n == start {
+                ", shape=doublecircle"
+            } else {
+                ""
+            };
+            writeln!(
+                out,
+                "        {} [label=\"{}\"{}];",
+                n.index(),
+                escape(&graph[n]),
+                shape
+            )
+            .unwrap();
+        }
+
+        writeln!(out, "    }}").unwrap();
+    }
+
+    for edge in graph.edge_references() {
+        writeln!(
+            out,
+            "    {} -> {} [label=\"{}\"];",
+            edge.source().index(),
+            edge.target().index(),
+            escape(edge.weight())
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> (Graph<&'static str, usize>, [NodeIndex; 3]) {
+        let mut graph = Graph::new();
+        let a = graph.add_node("say \"hi\"");
+        let b = graph.add_node("C:\\bags");
+        let c = graph.add_node("alone");
+        graph.add_edge(a, b, 3);
+
+        (graph, [a, b, c])
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let (graph, _) = graph();
+
+        assert_eq!(
+            to_dot(&graph, None),
+            "digraph {\n    0 [label=\"say \\\"hi\\\"\"];\n    1 [label=\"C:\\\\bags\"];\n    2 [label=\"alone\"];\n    0 -> 1 [label=\"3\"];\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_dot_highlight() {
+        let (graph, [a, _, _]) = graph();
+        let dot = to_dot(&graph, Some(a));
+
+        assert_eq!(
+            dot.lines().collect::<Vec<_>>(),
+            vec![
+                "digraph {",
+                "    2 [label=\"alone\"];",
+                "    subgraph cluster_reachable {",
+                "        label=\"reachable from say \\\"hi\\\"\";",
+                "        style=filled;",
+                "        color=lightgrey;",
+                "        0 [label=\"say \\\"hi\\\"\", shape=doublecircle];",
+                "        1 [label=\"C:\\\\bags\"];",
+                "    }",
+                "    0 -> 1 [label=\"3\"];",
+                "}",
+            ]
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+/// An error found while tokenizing or parsing, at a 1-based column of the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(column: usize, message: impl Into<String>) -> Self {
+        Self {
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl Operator {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '+' => Some(Operator::Add),
+            '-' => Some(Operator::Subtract),
+            '*' => Some(Operator::Multiply),
+            '/' => Some(Operator::Divide),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(self) -> char {
+        match self {
+            Operator::Add => '+',
+            Operator::Subtract => '-',
+            Operator::Multiply => '*',
+            Operator::Divide => '/',
+        }
+    }
+
+    /// Applies the operator, returning `None` on overflow or division by zero.
+    pub fn apply(self, lhs: i64, rhs: i64) -> Option<i64> {
+        match self {
+            Operator::Add => lhs.checked_add(rhs),
+            Operator::Subtract => lhs.checked_sub(rhs),
+            Operator::Multiply => lhs.checked_mul(rhs),
+            Operator::Divide => lhs.checked_div(rhs),
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Token {
+    Number(i64),
+    Operator(Operator),
+    LeftParen,
+    RightParen,
+}
+
+/// Splits `input` into tokens, each paired with the 1-based column it starts at.
+pub fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let column = i + 1;
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '0'..='9' => {
+                let mut end = i + 1;
+                while let Some(&(j, '0'..='9')) = chars.peek() {
+                    end = j + 1;
+                    chars.next();
+                }
+                let number = input[i..end]
+                    .parse()
+                    .map_err(|_| ParseError::new(column, "number is too large"))?;
+
+                Token::Number(number)
+            }
+            c => match Operator::from_char(c) {
+                Some(op) => Token::Operator(op),
+                None => return Err(ParseError::new(column, format!("unexpected {:?}", c))),
+            },
+        };
+
+        tokens.push((token, column));
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+/// The precedence and associativity of each operator the parser accepts.  Higher
+/// precedences bind more tightly; operators missing from the table are parse errors.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OperatorTable {
+    operators: HashMap<Operator, (u32, Associativity)>,
+}
+
+impl OperatorTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn operator(mut self, op: Operator, precedence: u32, associativity: Associativity) -> Self {
+        self.operators.insert(op, (precedence, associativity));
+        self
+    }
+
+    /// The usual rules: `*` and `/` bind more tightly than `+` and `-`, and all four
+    /// are left associative.
+    pub fn standard() -> Self {
+        Self::new()
+            .operator(Operator::Add, 1, Associativity::Left)
+            .operator(Operator::Subtract, 1, Associativity::Left)
+            .operator(Operator::Multiply, 2, Associativity::Left)
+            .operator(Operator::Divide, 2, Associativity::Left)
+    }
+
+    fn get(&self, op: Operator) -> Option<(u32, Associativity)> {
+        self.operators.get(&op).copied()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Expr {
+    Number(i64),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Evaluates the expression, returning `None` on overflow or division by zero.
+    pub fn eval(&self) -> Option<i64> {
+        match self {
+            Expr::Number(n) => Some(*n),
+            Expr::Binary(op, lhs, rhs) => op.apply(lhs.eval()?, rhs.eval()?),
+        }
+    }
+}
+
+impl Display for Expr {
+    /// Writes the expression fully parenthesised, which shows how it was parsed.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(n) => write!(f, "{}", n),
+            Expr::Binary(op, lhs, rhs) => write!(f, "({} {} {})", lhs, op, rhs),
+        }
+    }
+}
+
+/// A Pratt parser over the tokens of a single expression.
+struct Parser<'a> {
+    tokens: &'a [(Token, usize)],
+    position: usize,
+    table: &'a OperatorTable,
+    /// The column just past the end of the input, for errors at the end.
+    end: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<(Token, usize)> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn column(&self) -> usize {
+        self.peek().map_or(self.end, |(_, column)| column)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        match self.peek() {
+            Some((Token::Number(n), _)) => {
+                self.position += 1;
+                Ok(Expr::Number(n))
+            }
+            Some((Token::LeftParen, open)) => {
+                self.position += 1;
+                let inner = self.expression(0)?;
+
+                match self.peek() {
+                    Some((Token::RightParen, _)) => {
+                        self.position += 1;
+                        Ok(inner)
+                    }
+                    _ => Err(ParseError::new(
+                        self.column(),
+                        format!("expected `)` to close `(` at column {}", open),
+                    )),
+                }
+            }
+            _ => Err(ParseError::new(self.column(), "expected a number or `(`")),
+        }
+    }
+
+    /// Parses an expression containing only operators of at least `min_precedence`
+    /// outside of parentheses.
+    fn expression(&mut self, min_precedence: u32) -> Result<Expr, ParseError> {
+        let mut lhs = self.primary()?;
+
+        while let Some((Token::Operator(op), column)) = self.peek() {
+            let (precedence, associativity) = self.table.get(op).ok_or_else(|| {
+                ParseError::new(column, format!("operator `{}` is not supported", op))
+            })?;
+            if precedence < min_precedence {
+                break;
+            }
+            self.position += 1;
+
+            let next = match associativity {
+                Associativity::Left => precedence + 1,
+                Associativity::Right => precedence,
+            };
+            let rhs = self.expression(next)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+}
+
+/// Parses `input` into an expression tree using the operators in `table`.
+pub fn parse(input: &str, table: &OperatorTable) -> Result<Expr, ParseError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        position: 0,
+        table,
+        end: input.len() + 1,
+    };
+
+    let expr = parser.expression(0)?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some((Token::RightParen, column)) => Err(ParseError::new(column, "unmatched `)`")),
+        Some((_, column)) => Err(ParseError::new(column, "expected an operator")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precedence_and_associativity() {
+        let standard = OperatorTable::standard();
+        let expr = parse("1 + 2 * 3 - 8 / 4", &standard).unwrap();
+        assert_eq!(expr.to_string(), "((1 + (2 * 3)) - (8 / 4))");
+        assert_eq!(expr.eval(), Some(5));
+
+        let right = OperatorTable::new().operator(Operator::Subtract, 1, Associativity::Right);
+        assert_eq!(parse("8 - 2 - 1", &right).unwrap().eval(), Some(7));
+        assert_eq!(parse("8 - 2 - 1", &standard).unwrap().eval(), Some(5));
+        assert_eq!(parse("1 / 0", &standard).unwrap().eval(), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let table = OperatorTable::standard();
+        let error = |input| parse(input, &table).unwrap_err();
+
+        assert_eq!(
+            error("1 + * 2"),
+            ParseError {
+                column: 5,
+                message: "expected a number or `(`".to_string()
+            }
+        );
+        assert_eq!(
+            error("(1 + 2").to_string(),
+            "column 7: expected `)` to close `(` at column 1"
+        );
+        assert_eq!(error("1 + 2)").column, 6);
+        assert_eq!(error("1 2").column, 3);
+        assert_eq!(error("1 % 2").column, 3);
+
+        let additive = OperatorTable::new().operator(Operator::Add, 1, Associativity::Left);
+        assert_eq!(
+            parse("1 + 2 * 3", &additive).unwrap_err().to_string(),
+            "column 7: operator `*` is not supported"
+        );
+    }
+}
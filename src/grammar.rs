@@ -0,0 +1,287 @@
+use anyhow::{anyhow, bail, Context, Result};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Rule {
+    /// Matches exactly this character.
+    Char(char),
+    /// Matches any one of the sequences of rules.
+    Alternatives(Vec<Vec<usize>>),
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+
+        if let Some(quoted) = s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+            let mut chars = quoted.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(Rule::Char(c)),
+                _ => Err(anyhow!("expected a single character in {}", s)),
+            };
+        }
+
+        let alternatives = s
+            .split('|')
+            .map(|sequence| {
+                sequence
+                    .split_whitespace()
+                    .map(|id| {
+                        id.parse()
+                            .with_context(|| format!("invalid rule id {}", id))
+                    })
+                    .collect::<Result<Vec<usize>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if alternatives.iter().any(Vec::is_empty) {
+            bail!("empty alternative in {}", s);
+        }
+
+        Ok(Rule::Alternatives(alternatives))
+    }
+}
+
+/// A set of numbered rules, as in `0: 4 1 5`, `1: 2 3 | 3 2` and `4: "a"`.
+///
+/// Rules may refer to themselves, as long as every recursive reference comes after
+/// something which consumes input (e.g. `8: 42 | 42 8`); left recursion never
+/// terminates.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Grammar {
+    rules: HashMap<usize, Rule>,
+}
+
+impl Grammar {
+    /// Parses one rule per line, checking that every rule referred to exists.
+    pub fn parse<S: AsRef<str>>(lines: &[S]) -> Result<Self> {
+        let mut grammar = Self::default();
+
+        for line in lines {
+            let line = line.as_ref();
+            let colon = line
+                .find(':')
+                .ok_or_else(|| anyhow!("missing rule id in {}", line))?;
+            let id = line[..colon].trim().parse()?;
+
+            if grammar
+                .rules
+                .insert(id, line[colon + 1..].parse()?)
+                .is_some()
+            {
+                bail!("rule {} is defined twice", id);
+            }
+        }
+
+        grammar.check_references()?;
+        Ok(grammar)
+    }
+
+    fn check_references(&self) -> Result<()> {
+        for (id, rule) in &self.rules {
+            if let Rule::Alternatives(alternatives) = rule {
+                if let Some(missing) = alternatives
+                    .iter()
+                    .flatten()
+                    .find(|r| !self.rules.contains_key(r))
+                {
+                    bail!("rule {} refers to undefined rule {}", id, missing);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds or replaces rule `id`, leaving the grammar unchanged if `rule` refers to an
+    /// undefined rule.
+    pub fn set_rule(&mut self, id: usize, rule: Rule) -> Result<()> {
+        let previous = self.rules.insert(id, rule);
+
+        let result = self.check_references();
+        if result.is_err() {
+            match previous {
+                Some(previous) => self.rules.insert(id, previous),
+                None => self.rules.remove(&id),
+            };
+        }
+
+        result
+    }
+
+    /// Whether `rule` can reach itself by following references.
+    pub fn is_recursive(&self, rule: usize) -> bool {
+        let mut seen = HashSet::new();
+        let mut stack = self.references(rule);
+
+        while let Some(next) = stack.pop() {
+            if next == rule {
+                return true;
+            }
+            if seen.insert(next) {
+                stack.extend(self.references(next));
+            }
+        }
+
+        false
+    }
+
+    fn references(&self, rule: usize) -> Vec<usize> {
+        match &self.rules[&rule] {
+            Rule::Char(_) => Vec::new(),
+            Rule::Alternatives(alternatives) => alternatives.iter().flatten().copied().collect(),
+        }
+    }
+
+    /// Whether the whole of `message` matches `rule`.
+    pub fn matches(&self, rule: usize, message: &str) -> bool {
+        let message: Vec<char> = message.chars().collect();
+        self.ends(rule, &message, 0).contains(&message.len())
+    }
+
+    /// Every position at which a match of `rule` starting at `start` could end.  Keeping
+    /// them all lets a sequence backtrack into earlier rules, which rules like
+    /// `8: 42 | 42 8` followed by `11: 42 31 | 42 11 31` need.
+    fn ends(&self, rule: usize, message: &[char], start: usize) -> Vec<usize> {
+        match &self.rules[&rule] {
+            Rule::Char(c) => match message.get(start) {
+                Some(m) if m == c => vec![start + 1],
+                _ => Vec::new(),
+            },
+            Rule::Alternatives(alternatives) => {
+                let mut ends = Vec::new();
+
+                for sequence in alternatives {
+                    let mut positions = vec![start];
+                    for &part in sequence {
+                        positions = positions
+                            .into_iter()
+                            .flat_map(|p| self.ends(part, message, p))
+                            .collect();
+                        positions.sort_unstable();
+                        positions.dedup();
+                    }
+                    ends.extend(positions);
+                }
+
+                ends.sort_unstable();
+                ends.dedup();
+                ends
+            }
+        }
+    }
+
+    /// A regex matching exactly the messages matching `rule`, which must not depend on
+    /// any recursive rules.
+    pub fn to_regex(&self, rule: usize) -> Result<Regex> {
+        let mut reachable = vec![rule];
+        let mut seen = HashSet::new();
+        while let Some(next) = reachable.pop() {
+            if !seen.insert(next) {
+                continue;
+            }
+            if self.is_recursive(next) {
+                bail!(
+                    "rule {} is recursive and cannot be written as a regex",
+                    next
+                );
+            }
+            reachable.extend(self.references(next));
+        }
+
+        let mut patterns = HashMap::new();
+        let pattern = self.pattern(rule, &mut patterns);
+
+        Ok(Regex::new(&format!("^{}$", pattern))?)
+    }
+
+    fn pattern(&self, rule: usize, patterns: &mut HashMap<usize, String>) -> String {
+        if let Some(pattern) = patterns.get(&rule) {
+            return pattern.clone();
+        }
+
+        let pattern = match &self.rules[&rule] {
+            Rule::Char(c) => regex::escape(&c.to_string()),
+            Rule::Alternatives(alternatives) => {
+                let choices: Vec<String> = alternatives
+                    .iter()
+                    .map(|sequence| {
+                        sequence
+                            .iter()
+                            .map(|&part| self.pattern(part, patterns))
+                            .collect()
+                    })
+                    .collect();
+
+                if choices.len() == 1 {
+                    choices.into_iter().next().unwrap()
+                } else {
+                    format!("(?:{})", choices.join("|"))
+                }
+            }
+        };
+
+        patterns.insert(rule, pattern.clone());
+        pattern
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Grammar {
+        Grammar::parse(&[
+            "0: 4 1 5",
+            "1: 2 3 | 3 2",
+            "2: 4 4 | 5 5",
+            "3: 4 5 | 5 4",
+            "4: \"a\"",
+            "5: \"b\"",
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_matcher_agrees_with_regex() {
+        let mut grammar = example();
+        let regex = grammar.to_regex(0).unwrap();
+
+        for bits in 0..1 << 6 {
+            let message: String = (0..6)
+                .map(|i| if bits >> i & 1 == 1 { 'b' } else { 'a' })
+                .collect();
+            assert_eq!(grammar.matches(0, &message), regex.is_match(&message));
+        }
+        assert!(grammar.matches(0, "ababbb"));
+        assert!(!grammar.matches(0, "aaaabbb"));
+
+        grammar
+            .set_rule(2, "4 4 | 5 5 | 4 2".parse().unwrap())
+            .unwrap();
+        assert!(grammar.is_recursive(2));
+        assert!(!grammar.is_recursive(0));
+        assert!(grammar.matches(0, "aaaaabb"));
+        assert_eq!(
+            grammar.to_regex(0).unwrap_err().to_string(),
+            "rule 2 is recursive and cannot be written as a regex"
+        );
+
+        assert!(grammar
+            .set_rule(3, Rule::Alternatives(vec![vec![6]]))
+            .is_err());
+        assert_eq!(grammar.rules[&3], "4 5 | 5 4".parse().unwrap());
+    }
+
+    #[test]
+    fn test_invalid_grammars() {
+        assert!(Grammar::parse(&["0: 1 2", "1: \"a\""]).is_err());
+        assert!(Grammar::parse(&["0: \"ab\""]).is_err());
+        assert!(Grammar::parse(&["0: 1 |", "1: \"a\""]).is_err());
+        assert!(Grammar::parse(&["0: \"a\"", "0: \"b\""]).is_err());
+    }
+}
@@ -65,6 +65,12 @@ pub struct Movement {
     steps: usize,
 }
 
+impl Movement {
+    pub fn new(direction: Direction, steps: usize) -> Self {
+        Self { direction, steps }
+    }
+}
+
 impl FromStr for Movement {
     type Err = anyhow::Error;
 
@@ -121,6 +127,11 @@ impl Position {
 
         abs(self.x) + abs(self.y)
     }
+
+    /// Rotates the position a quarter turn anticlockwise about the origin.
+    pub fn rotate_left(&self) -> Self {
+        Self::new(-self.y, self.x)
+    }
 }
 
 impl Sub for Position {
@@ -181,6 +192,25 @@ impl GridVisitor for HistoryVisitor {
     }
 }
 
+/// A visitor which ignores every move, for when only the final position matters.
+#[derive(Debug, Clone, Default)]
+pub struct NullVisitor;
+
+impl GridVisitor for NullVisitor {
+    type Output = ();
+
+    fn go(
+        &mut self,
+        _pos: Position,
+        _direction: Direction,
+        _movement: Movement,
+        _step_index: usize,
+    ) {
+    }
+
+    fn process(self: Box<Self>) {}
+}
+
 /// A struct that keeps track of how many steps you've made the first time you reach
 /// a point.
 #[derive(Debug, Clone, Default)]
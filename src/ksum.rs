@@ -0,0 +1,263 @@
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// How `k_sum` looks for combinations.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Strategy {
+    /// Fix all but two entries, then scan the remaining entries in sorted order
+    /// from both ends.
+    TwoPointer,
+    /// Fix all but two entries, then look up each entry's complement in a hash map.
+    Hash,
+    /// Tabulate the sums of every `k / 2` entries, then look up the complement of
+    /// every `k - k / 2` entries.  Best suited to larger `k`.
+    MeetInTheMiddle,
+}
+
+/// Finds every combination of `k` distinct indices into `nums` whose entries sum to
+/// `target`.  Each combination is returned once, with its indices in increasing order;
+/// equal entries at different indices give rise to different combinations.
+pub fn k_sum(nums: &[i64], k: usize, target: i64, strategy: Strategy) -> Vec<Vec<usize>> {
+    let mut found = Vec::new();
+    search(nums, k, target, strategy, &mut |c| {
+        found.push(c);
+        true
+    });
+
+    found
+}
+
+/// Like `k_sum`, but stops at the first combination found.
+pub fn first_k_sum(nums: &[i64], k: usize, target: i64, strategy: Strategy) -> Option<Vec<usize>> {
+    let mut first = None;
+    search(nums, k, target, strategy, &mut |c| {
+        first = Some(c);
+        false
+    });
+
+    first
+}
+
+/// Passes every combination found to `emit`, stopping early if it returns false.
+type Emit<'a> = dyn FnMut(Vec<usize>) -> bool + 'a;
+
+fn search(nums: &[i64], k: usize, target: i64, strategy: Strategy, emit: &mut Emit) {
+    if strategy == Strategy::MeetInTheMiddle {
+        meet_in_the_middle(nums, k, target, emit);
+        return;
+    }
+
+    let mut order: Vec<usize> = (0..nums.len()).collect();
+    if strategy == Strategy::TwoPointer {
+        order.sort_by_key(|&i| nums[i]);
+    }
+
+    let mut chosen = Vec::with_capacity(k);
+    fix(nums, &order, k, target, strategy, &mut chosen, emit);
+}
+
+fn found(chosen: &[usize], rest: &[usize], emit: &mut Emit) -> bool {
+    let mut combination: Vec<usize> = chosen.iter().chain(rest).copied().collect();
+    combination.sort_unstable();
+    emit(combination)
+}
+
+/// Fixes entries one at a time (each after the previously fixed one in `order`) until
+/// only a pair remains.  Returns false once `emit` has asked us to stop.
+fn fix(
+    nums: &[i64],
+    order: &[usize],
+    k: usize,
+    target: i64,
+    strategy: Strategy,
+    chosen: &mut Vec<usize>,
+    emit: &mut Emit,
+) -> bool {
+    match k {
+        0 => target != 0 || found(chosen, &[], emit),
+        1 => order
+            .iter()
+            .filter(|&&i| nums[i] == target)
+            .all(|&i| found(chosen, &[i], emit)),
+        2 if strategy == Strategy::TwoPointer => two_pointer(nums, order, target, chosen, emit),
+        2 => hash_pairs(nums, order, target, chosen, emit),
+        _ => (0..order.len()).all(|p| {
+            chosen.push(order[p]);
+            let more = fix(
+                nums,
+                &order[p + 1..],
+                k - 1,
+                target - nums[order[p]],
+                strategy,
+                chosen,
+                emit,
+            );
+            chosen.pop();
+            more
+        }),
+    }
+}
+
+/// Finds the pairs summing to `target`, where `order` is sorted by value.
+fn two_pointer(
+    nums: &[i64],
+    order: &[usize],
+    target: i64,
+    chosen: &[usize],
+    emit: &mut Emit,
+) -> bool {
+    if order.len() < 2 {
+        return true;
+    }
+
+    let value = |p: usize| nums[order[p]];
+    let (mut l, mut r) = (0, order.len() - 1);
+
+    while l < r {
+        let sum = value(l) + value(r);
+
+        if sum < target {
+            l += 1;
+        } else if sum > target {
+            r -= 1;
+        } else if value(l) == value(r) {
+            // Every entry between the pointers is equal, so any two of them will do.
+            return (l..=r)
+                .tuple_combinations()
+                .all(|(i, j)| found(chosen, &[order[i], order[j]], emit));
+        } else {
+            // Pair up every copy of the left value with every copy of the right value.
+            let (lv, rv) = (value(l), value(r));
+            // value(r) != lv and value(l) != rv, so both searches succeed.
+            let l_end = (l..=r).find(|&p| value(p) != lv).unwrap();
+            let r_end = (l..=r).rev().find(|&p| value(p) != rv).unwrap();
+
+            for i in l..l_end {
+                for j in r_end + 1..=r {
+                    if !found(chosen, &[order[i], order[j]], emit) {
+                        return false;
+                    }
+                }
+            }
+
+            l = l_end;
+            r = r_end;
+        }
+    }
+
+    true
+}
+
+/// Finds the pairs summing to `target` by remembering the entries seen so far.
+fn hash_pairs(
+    nums: &[i64],
+    order: &[usize],
+    target: i64,
+    chosen: &[usize],
+    emit: &mut Emit,
+) -> bool {
+    let mut seen: HashMap<i64, Vec<usize>> = HashMap::new();
+
+    for &j in order {
+        if let Some(matches) = seen.get(&(target - nums[j])) {
+            for &i in matches {
+                if !found(chosen, &[i, j], emit) {
+                    return false;
+                }
+            }
+        }
+        seen.entry(nums[j]).or_default().push(j);
+    }
+
+    true
+}
+
+fn meet_in_the_middle(nums: &[i64], k: usize, target: i64, emit: &mut Emit) -> bool {
+    let (a, b) = (k / 2, k - k / 2);
+
+    let mut halves: HashMap<i64, Vec<Vec<usize>>> = HashMap::new();
+    for first in (0..nums.len()).combinations(a) {
+        let sum = first.iter().map(|&i| nums[i]).sum();
+        halves.entry(sum).or_default().push(first);
+    }
+
+    for second in (0..nums.len()).combinations(b) {
+        let sum: i64 = second.iter().map(|&i| nums[i]).sum();
+
+        if let Some(firsts) = halves.get(&(target - sum)) {
+            // Only combine halves where every index of the first precedes every index of
+            // the second, so each combination is produced exactly once.
+            for first in firsts {
+                let ordered = match (first.last(), second.first()) {
+                    (Some(x), Some(y)) => x < y,
+                    _ => true,
+                };
+
+                if ordered && !found(first, &second, emit) {
+                    return false;
+                }
+            }
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_problem_input;
+
+    #[test]
+    fn test_strategies_agree() {
+        let nums: Vec<i64> = load_problem_input(1).parse();
+        let strategies = [
+            Strategy::TwoPointer,
+            Strategy::Hash,
+            Strategy::MeetInTheMiddle,
+        ];
+
+        for k in 1..=3 {
+            let mut results = strategies.iter().map(|&strategy| {
+                let mut found = k_sum(&nums, k, 2020, strategy);
+                found.sort();
+                found
+            });
+
+            let first = results.next().unwrap();
+            assert!(results.all(|r| r == first));
+        }
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let nums = vec![1010, 5, 1010, 1010, 2015];
+
+        for &strategy in [
+            Strategy::TwoPointer,
+            Strategy::Hash,
+            Strategy::MeetInTheMiddle,
+        ]
+        .iter()
+        {
+            let mut found = k_sum(&nums, 2, 2020, strategy);
+            found.sort();
+            assert_eq!(found, vec![vec![0, 2], vec![0, 3], vec![1, 4], vec![2, 3]]);
+        }
+
+        for &(nums, k, target) in [
+            (&[1, 3][..], 2, 4),
+            (&[1, 1, 3][..], 2, 4),
+            (&[0, 1, 3][..], 3, 4),
+        ]
+        .iter()
+        {
+            let mut expected = k_sum(nums, k, target, Strategy::Hash);
+            expected.sort();
+            let mut found = k_sum(nums, k, target, Strategy::TwoPointer);
+            found.sort();
+            assert!(!expected.is_empty());
+            assert_eq!(found, expected);
+        }
+    }
+}
@@ -1,12 +1,20 @@
-#![feature(iterator_fold_self)]
 #![feature(map_first_last)]
 
 use anyhow::{Context, Result};
 
 use std::path::Path;
 
+pub mod charset;
+pub mod containment;
+pub mod dot;
+pub mod expression;
+pub mod grammar;
 pub mod grid;
+pub mod ksum;
+pub mod number_theory;
 pub mod questions;
+pub mod records;
+pub mod validation;
 
 pub fn load_problem_input(number: usize) -> ProblemInput {
     let path = format!("data/q{}.txt", number);
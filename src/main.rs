@@ -1,4 +1,10 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+use aoc2020::containment::ContainmentGraph;
+use aoc2020::dot::to_dot;
+use aoc2020::questions::q2::{explain, Policy};
+use aoc2020::questions::q4::diagnostics_report;
+use aoc2020::questions::q7::Bag;
+use aoc2020::questions::q8::{ControlFlowGraph, Instruction};
 use aoc2020::questions::*;
 use aoc2020::{ProblemInput, Solution};
 use crossbeam::thread;
@@ -32,7 +38,99 @@ fn run_problem(solutions: &'static [Box<dyn Solution>], index: usize) -> Result<
     Ok((part1.unwrap(), part2.unwrap()))
 }
 
+/// Prints the graph built from a problem's input in DOT format.
+///
+/// Usage: `dot <problem> [start]`, where `start` optionally names a node (a bag for
+/// problem 7, an instruction index for problem 8) whose reachable nodes are highlighted.
+fn run_dot(args: &[String]) -> Result<()> {
+    let problem: usize = args
+        .first()
+        .ok_or_else(|| anyhow!("usage: dot <problem> [start]"))?
+        .parse()?;
+    let start = if args.len() > 1 {
+        Some(args[1..].join(" "))
+    } else {
+        None
+    };
+
+    let problem_input = ProblemInput::new(format!("data/q{}.txt", problem))?;
+
+    let dot = match problem {
+        7 => {
+            let g = problem_input.parse::<ContainmentGraph<Bag>>();
+            let highlight = match start {
+                Some(bag) => Some(g.node(&bag.parse()?)?),
+                None => None,
+            };
+            to_dot(&g.graph, highlight)
+        }
+        8 => {
+            let instructions = problem_input.parse::<Vec<Instruction>>();
+            let cfg = ControlFlowGraph::new(&instructions);
+            let highlight = match start {
+                Some(pc) => {
+                    let pc: usize = pc.parse()?;
+                    if pc >= instructions.len() {
+                        bail!("no instruction {}", pc);
+                    }
+                    Some(cfg.node(pc))
+                }
+                None => None,
+            };
+            to_dot(&cfg.graph, highlight)
+        }
+        _ => bail!("no graph available for problem {}", problem),
+    };
+
+    print!("{}", dot);
+    Ok(())
+}
+
+/// Prints a report explaining why records in a problem's input were rejected.
+///
+/// Usage: `diagnose <problem>`.
+fn run_diagnose(args: &[String]) -> Result<()> {
+    let problem: usize = args
+        .first()
+        .ok_or_else(|| anyhow!("usage: diagnose <problem>"))?
+        .parse()?;
+
+    let problem_input = ProblemInput::new(format!("data/q{}.txt", problem))?;
+
+    let report = match problem {
+        4 => diagnostics_report(&problem_input),
+        _ => bail!("no diagnostics available for problem {}", problem),
+    };
+
+    print!("{}", report);
+    Ok(())
+}
+
+/// Checks the problem 2 passwords against a policy, explaining each verdict.
+///
+/// Usage: `policy <name>`, where `name` is `count`, `one-position`, `all-positions`
+/// or `regex:<pattern>`.
+fn run_policy(args: &[String]) -> Result<()> {
+    let policy: Policy = args
+        .first()
+        .ok_or_else(|| anyhow!("usage: policy <name>"))?
+        .parse()?;
+
+    let problem_input = ProblemInput::new("data/q2.txt")?;
+
+    print!("{}", explain(&problem_input, &policy));
+    Ok(())
+}
+
 fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("dot") => return run_dot(&args[1..]),
+        Some("diagnose") => return run_diagnose(&args[1..]),
+        Some("policy") => return run_policy(&args[1..]),
+        _ => {}
+    }
+
     let solutions: Vec<Box<dyn Solution>> = vec![
         Box::new(Q1 {}),
         Box::new(Q2 {}),
@@ -0,0 +1,229 @@
+use anyhow::{anyhow, bail, Result};
+use num::{BigInt, Integer, One, Signed, ToPrimitive, Zero};
+
+/// Returns `(g, x, y)` with `g = gcd(a, b) >= 0` and `a * x + b * y = g`.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+
+    while r != 0 {
+        let q = old_r / r;
+        let next = (r, old_r - q * r);
+        old_r = next.0;
+        r = next.1;
+        let next = (x, old_x - q * x);
+        old_x = next.0;
+        x = next.1;
+        let next = (y, old_y - q * y);
+        old_y = next.0;
+        y = next.1;
+    }
+
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// `a * b mod m`, in `[0, m)`, without overflowing even when `m` is close to `i128::MAX`.
+pub fn mul_mod(a: i128, b: i128, m: i128) -> i128 {
+    let (a, b) = (a.rem_euclid(m), b.rem_euclid(m));
+
+    match a.checked_mul(b) {
+        Some(product) => product % m,
+        None => (BigInt::from(a) * BigInt::from(b) % BigInt::from(m))
+            .to_i128()
+            .unwrap(),
+    }
+}
+
+/// `a + b mod m` for `a` and `b` in `[0, m)`, without overflowing even when `m` is
+/// close to `i128::MAX`.
+fn add_mod(a: i128, b: i128, m: i128) -> i128 {
+    if a >= m - b {
+        a - (m - b)
+    } else {
+        a + b
+    }
+}
+
+/// The inverse of `a` modulo `m`, if `a` and `m` are coprime.
+pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m);
+
+    if g == 1 {
+        Some(x.rem_euclid(m))
+    } else {
+        None
+    }
+}
+
+/// `base ^ exp mod m`, by repeated squaring.
+pub fn mod_pow(base: i128, mut exp: u128, m: i128) -> i128 {
+    let mut result = 1 % m;
+    let mut base = base.rem_euclid(m);
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+
+    result
+}
+
+fn check_moduli(congruences: &[(i128, i128)]) -> Result<()> {
+    if let Some(&(_, m)) = congruences.iter().find(|&&(_, m)| m <= 0) {
+        bail!("modulus must be positive: {}", m);
+    }
+
+    Ok(())
+}
+
+/// Solves `x = a (mod m)` for every `(a, m)` in `congruences`, whose moduli must be
+/// pairwise coprime, returning `(x, M)` where `M` is the product of the moduli.
+pub fn crt_coprime(congruences: &[(i128, i128)]) -> Result<(i128, i128)> {
+    check_moduli(congruences)?;
+
+    let modulus = congruences.iter().try_fold(1_i128, |acc, &(_, m)| {
+        acc.checked_mul(m)
+            .ok_or_else(|| anyhow!("product of moduli overflows"))
+    })?;
+
+    let mut x = 0;
+    for &(a, m) in congruences {
+        let rest = modulus / m;
+        let inverse = mod_inverse(rest, m)
+            .ok_or_else(|| anyhow!("moduli are not pairwise coprime: {}", m))?;
+        let term = mul_mod(mul_mod(a, inverse, modulus), rest, modulus);
+        x = add_mod(x, term, modulus);
+    }
+
+    Ok((x, modulus))
+}
+
+/// Solves `x = a (mod m)` for every `(a, m)` in `congruences`, returning `(x, L)` where
+/// `L` is the lcm of the moduli.  The moduli need not be coprime; an error is returned
+/// if the congruences are inconsistent or the lcm does not fit in an `i128`.
+pub fn crt(congruences: &[(i128, i128)]) -> Result<(i128, i128)> {
+    check_moduli(congruences)?;
+
+    let mut x: i128 = 0;
+    let mut modulus: i128 = 1;
+
+    for &(a, m) in congruences {
+        // Find x + modulus * t = a (mod m): solvable only if gcd(modulus, m) | a - x.
+        let (g, inverse, _) = extended_gcd(modulus, m);
+        let diff = a.rem_euclid(m) - x.rem_euclid(m);
+        if diff % g != 0 {
+            bail!("inconsistent congruences: x = {} (mod {})", a, m);
+        }
+
+        let step = m / g;
+        let t = mul_mod(diff / g, inverse, step);
+        let lcm = modulus
+            .checked_mul(step)
+            .ok_or_else(|| anyhow!("lcm of moduli overflows"))?;
+
+        // t < step, so this is below the lcm and cannot overflow.
+        x += modulus * t;
+        modulus = lcm;
+    }
+
+    Ok((x, modulus))
+}
+
+/// As `crt`, using arbitrary precision so the lcm of the moduli can be any size.
+pub fn crt_big(congruences: &[(BigInt, BigInt)]) -> Result<(BigInt, BigInt)> {
+    if let Some((_, m)) = congruences.iter().find(|(_, m)| !m.is_positive()) {
+        bail!("modulus must be positive: {}", m);
+    }
+
+    let mut x = BigInt::zero();
+    let mut modulus = BigInt::one();
+
+    for (a, m) in congruences {
+        let egcd = modulus.extended_gcd(m);
+        let g = egcd.gcd.abs();
+        let diff = a.mod_floor(m) - x.mod_floor(m);
+        if !diff.is_multiple_of(&g) {
+            bail!("inconsistent congruences: x = {} (mod {})", a, m);
+        }
+
+        let step = m / &g;
+        let t = ((diff / &g) * egcd.x).mod_floor(&step);
+        let lcm = &modulus * &step;
+
+        x = (x + &modulus * t).mod_floor(&lcm);
+        modulus = lcm;
+    }
+
+    Ok((x, modulus))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modular_arithmetic() {
+        assert_eq!(extended_gcd(240, 46), (2, -9, 47));
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(4, 6), None);
+        assert_eq!(mod_pow(4, 13, 497), 445);
+        assert_eq!(mod_pow(3, 200, i128::MAX), {
+            let big = BigInt::from(3).modpow(&BigInt::from(200), &BigInt::from(i128::MAX));
+            big.to_i128().unwrap()
+        });
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt_coprime(&[(2, 3), (3, 5), (2, 7)]).unwrap(), (23, 105));
+        assert!(crt_coprime(&[(2, 4), (4, 6)]).is_err());
+        assert_eq!(crt(&[(2, 4), (4, 6)]).unwrap(), (10, 12));
+        assert!(crt(&[(1, 4), (2, 6)]).is_err());
+
+        for congruences in &[[(1, 3), (0, 0)], [(1, 3), (2, -5)]] {
+            let m = congruences[1].1;
+            let message = format!("modulus must be positive: {}", m);
+            assert_eq!(crt_coprime(congruences).unwrap_err().to_string(), message);
+            assert_eq!(crt(congruences).unwrap_err().to_string(), message);
+
+            let big: Vec<_> = congruences
+                .iter()
+                .map(|&(a, m)| (BigInt::from(a), BigInt::from(m)))
+                .collect();
+            assert_eq!(crt_big(&big).unwrap_err().to_string(), message);
+        }
+
+        // The product of these primes only just fits in an i128.
+        let (p, q) = ((1 << 64) - 59, (1 << 63) - 25);
+        let congruences = [(p - 1, p), (q - 1, q)];
+        assert_eq!(crt_coprime(&congruences).unwrap(), (p * q - 1, p * q));
+        assert_eq!(crt(&congruences).unwrap(), (p * q - 1, p * q));
+    }
+
+    #[test]
+    fn test_crt_big() {
+        // The product of these primes does not fit in an i128.
+        let primes: [i128; 3] = [
+            1_000_000_000_000_000_003,
+            1_000_000_000_000_000_009,
+            999_999_999_999_999_989,
+        ];
+        let congruences: Vec<_> = primes.iter().map(|&p| (p - 1, p)).collect();
+        assert!(crt(&congruences).is_err());
+
+        let big: Vec<_> = congruences
+            .iter()
+            .map(|&(a, m)| (BigInt::from(a), BigInt::from(m)))
+            .collect();
+        let (x, modulus) = crt_big(&big).unwrap();
+        assert_eq!(x, &modulus - 1);
+    }
+}
@@ -1,43 +1,29 @@
+use crate::ksum::{first_k_sum, Strategy};
 use crate::{ProblemInput, Solution};
-use std::collections::HashSet;
 
 pub struct Q1;
 
-fn two_sum(n: &[i64], target: i64, skip_index: Option<usize>) -> Option<i64> {
-    let mut seen = HashSet::new();
+/// Finds `k` entries summing to 2020 and multiplies them together.
+fn solve(lines: &ProblemInput, k: usize, strategy: Strategy) -> String {
+    let nums: Vec<i64> = lines.parse();
 
-    for (i, x) in n.iter().copied().enumerate() {
-        if Some(i) == skip_index {
-            continue;
-        }
-
-        if seen.contains(&(target - x)) {
-            return Some(x);
-        }
-        seen.insert(x);
-    }
-
-    None
+    first_k_sum(&nums, k, 2020, strategy)
+        .unwrap()
+        .into_iter()
+        .map(|i| nums[i])
+        .product::<i64>()
+        .to_string()
 }
 
 impl Solution for Q1 {
     /// Find the two entries that sum to 2020 and multiply them together.
     fn part1(&self, lines: &ProblemInput) -> String {
-        let x = two_sum(lines.parse::<Vec<i64>>().as_slice(), 2020, None).unwrap();
-        (x * (2020 - x)).to_string()
+        solve(lines, 2, Strategy::Hash)
     }
 
     /// Find the three entries that sum to 2020 and multiply them together.
     fn part2(&self, lines: &ProblemInput) -> String {
-        let nums: Vec<i64> = lines.parse();
-
-        for (i, x) in nums.iter().copied().enumerate() {
-            if let Some(y) = two_sum(nums.as_slice(), 2020 - x, Some(i)) {
-                return (x * y * (2020 - x - y)).to_string();
-            }
-        }
-
-        unreachable!()
+        solve(lines, 3, Strategy::TwoPointer)
     }
 }
 
@@ -1,38 +1,174 @@
 use crate::{ProblemInput, Solution};
-use std::collections::HashMap;
+use num::{BigUint, One, Zero};
+use std::collections::{BTreeMap, BTreeSet};
 
 pub struct Q10;
 
-impl Solution for Q10 {
-    fn part1(&self, lines: &ProblemInput) -> String {
-        let mut adapters = lines.parse::<Vec<i64>>();
-        adapters.push(0);
-        adapters.sort_unstable();
+/// A bag of adapters, chained from the outlet (0 jolts) to the device, where each
+/// step up in joltage must be one of the allowed gaps.  The device is rated the
+/// largest allowed gap above the highest adapter.
+pub struct AdapterChain {
+    joltages: Vec<i64>,
+    allowed_gaps: BTreeSet<i64>,
+    max_gap: i64,
+    // For each joltage, the number of ways of getting from it to the device.
+    to_end: Vec<BigUint>,
+}
+
+impl AdapterChain {
+    pub fn new(adapters: &[i64], allowed_gaps: &[i64]) -> Self {
+        let allowed_gaps: BTreeSet<i64> = allowed_gaps.iter().copied().collect();
+        let max_gap = *allowed_gaps.iter().next_back().expect("no allowed gaps");
+        assert!(
+            *allowed_gaps.iter().next().unwrap() > 0,
+            "gaps must be positive"
+        );
+
+        let mut joltages = adapters.to_vec();
+        joltages.push(0);
+        joltages.sort_unstable();
+        joltages.push(joltages[joltages.len() - 1] + max_gap);
+
+        let mut chain = Self {
+            joltages,
+            allowed_gaps,
+            max_gap,
+            to_end: Vec::new(),
+        };
+
+        let last = chain.joltages.len() - 1;
+        let mut to_end = vec![BigUint::zero(); chain.joltages.len()];
+        to_end[last] = BigUint::one();
+        for i in (0..last).rev() {
+            to_end[i] = chain.successors(i).map(|c| &to_end[c]).sum();
+        }
+        chain.to_end = to_end;
+
+        chain
+    }
 
-        let ones = adapters.windows(2).filter(|w| w[1] - w[0] == 1).count();
+    /// The joltages which can directly follow the joltage at index `i`.
+    fn successors(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        let from = self.joltages[i];
 
-        (ones * (adapters.len() - ones)).to_string()
+        (i + 1..self.joltages.len())
+            .take_while(move |&c| self.joltages[c] - from <= self.max_gap)
+            .filter(move |&c| self.allowed_gaps.contains(&(self.joltages[c] - from)))
     }
 
-    fn part2(&self, lines: &ProblemInput) -> String {
-        let mut adapters = lines.parse::<Vec<i64>>();
-        adapters.push(0);
-        adapters.sort_unstable();
-
-        let mut map = HashMap::new();
-        map.insert(0, 1);
-
-        for &n in &adapters {
-            match map.get(&n).copied() {
-                Some(curr) if curr > 0 => {
-                    for target in (n + 1)..=(n + 3) {
-                        *map.entry(target).or_default() += curr as i64;
-                    }
+    /// How many times each gap occurs when every adapter is used.
+    pub fn gap_histogram(&self) -> BTreeMap<i64, usize> {
+        let mut histogram = BTreeMap::new();
+        for w in self.joltages.windows(2) {
+            *histogram.entry(w[1] - w[0]).or_default() += 1;
+        }
+
+        histogram
+    }
+
+    pub fn count_arrangements(&self) -> BigUint {
+        self.to_end[0].clone()
+    }
+
+    /// Lazily enumerates every valid arrangement, as the joltages it passes through.
+    pub fn arrangements(&self) -> Arrangements<'_> {
+        let (path, cursors) = if self.to_end[0].is_zero() {
+            (vec![], vec![])
+        } else {
+            (vec![0], vec![1])
+        };
+
+        Arrangements {
+            chain: self,
+            path,
+            cursors,
+        }
+    }
+
+    /// The arrangement `arrangements()` would produce after skipping `n` others,
+    /// found without enumerating them.  Picking `n` at random gives a uniform sample.
+    pub fn nth_arrangement(&self, n: &BigUint) -> Option<Vec<i64>> {
+        if *n >= self.to_end[0] {
+            return None;
+        }
+
+        let mut n = n.clone();
+        let mut path = vec![0];
+        let mut top = 0;
+
+        while top != self.joltages.len() - 1 {
+            for c in self.successors(top) {
+                if n < self.to_end[c] {
+                    top = c;
+                    break;
+                }
+                n -= &self.to_end[c];
+            }
+            path.push(top);
+        }
+
+        Some(path.into_iter().map(|i| self.joltages[i]).collect())
+    }
+}
+
+/// An iterator over the valid arrangements of an `AdapterChain`.
+pub struct Arrangements<'a> {
+    chain: &'a AdapterChain,
+    // The indices of the joltages in the arrangement being built, and for each
+    // the index of the next successor to try.
+    path: Vec<usize>,
+    cursors: Vec<usize>,
+}
+
+impl Iterator for Arrangements<'_> {
+    type Item = Vec<i64>;
+
+    fn next(&mut self) -> Option<Vec<i64>> {
+        let chain = self.chain;
+        let last = chain.joltages.len() - 1;
+
+        while let Some(&top) = self.path.last() {
+            if top == last {
+                let arrangement = self.path.iter().map(|&i| chain.joltages[i]).collect();
+                self.path.pop();
+                self.cursors.pop();
+                return Some(arrangement);
+            }
+
+            let cursor = self.cursors.last_mut().unwrap();
+            let next = chain
+                .successors(top)
+                .find(|&c| c >= *cursor && !chain.to_end[c].is_zero());
+
+            match next {
+                Some(c) => {
+                    *cursor = c + 1;
+                    self.path.push(c);
+                    self.cursors.push(c + 1);
+                }
+                None => {
+                    self.path.pop();
+                    self.cursors.pop();
                 }
-                _ => {}
             }
         }
-        map[&adapters[adapters.len() - 1]].to_string()
+
+        None
+    }
+}
+
+impl Solution for Q10 {
+    fn part1(&self, lines: &ProblemInput) -> String {
+        let histogram = AdapterChain::new(&lines.parse::<Vec<i64>>(), &[1, 2, 3]).gap_histogram();
+        let count = |gap| histogram.get(&gap).copied().unwrap_or(0);
+
+        (count(1) * count(3)).to_string()
+    }
+
+    fn part2(&self, lines: &ProblemInput) -> String {
+        AdapterChain::new(&lines.parse::<Vec<i64>>(), &[1, 2, 3])
+            .count_arrangements()
+            .to_string()
     }
 }
 
@@ -55,4 +191,40 @@ mod tests {
             3022415986688_i64.to_string()
         );
     }
+
+    #[test]
+    fn test_arrangements() {
+        let chain = AdapterChain::new(&[16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4], &[1, 2, 3]);
+        let arrangements: Vec<_> = chain.arrangements().collect();
+
+        assert_eq!(
+            chain.gap_histogram().into_iter().collect::<Vec<_>>(),
+            vec![(1, 7), (3, 5)]
+        );
+        assert_eq!(chain.count_arrangements(), BigUint::from(8_u32));
+        assert_eq!(arrangements.len(), 8);
+        assert!(arrangements
+            .iter()
+            .all(|a| a[0] == 0 && a[a.len() - 1] == 22));
+
+        for (n, arrangement) in arrangements.iter().enumerate() {
+            assert_eq!(
+                chain.nth_arrangement(&BigUint::from(n)).as_ref(),
+                Some(arrangement)
+            );
+        }
+        assert_eq!(chain.nth_arrangement(&BigUint::from(8_u32)), None);
+    }
+
+    #[test]
+    fn test_large_counts() {
+        let adapters: Vec<i64> = (1..=200).collect();
+        let chain = AdapterChain::new(&adapters, &[1, 2, 3]);
+
+        assert!(chain.count_arrangements() > BigUint::from(i64::MAX as u64));
+        assert_eq!(
+            AdapterChain::new(&[2, 4], &[1, 3]).count_arrangements(),
+            BigUint::zero()
+        );
+    }
 }
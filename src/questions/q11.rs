@@ -1,31 +1,257 @@
-use crate::{ProblemInput, Solution};
+use crate::{FromProblemInput, ProblemInput, Solution};
+use std::fmt::{self, Display, Formatter};
 
 pub struct Q11;
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Cell {
+    Floor,
+    Empty,
+    Occupied,
+}
+
+impl Cell {
+    fn from_char(c: char) -> Self {
+        match c {
+            '.' => Cell::Floor,
+            'L' => Cell::Empty,
+            '#' => Cell::Occupied,
+            c => panic!("invalid cell {}", c),
+        }
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            Cell::Floor => '.',
+            Cell::Empty => 'L',
+            Cell::Occupied => '#',
+        }
+    }
+}
+
+/// Which seats a passenger looks at when deciding whether to move.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Neighbourhood {
+    /// The (up to) eight seats immediately around them.
+    Adjacent,
+    /// The first seat visible in each of the eight directions, looking past floor.
+    LineOfSight,
+}
+
+/// An empty seat becomes occupied if none of its neighbours are occupied, and an
+/// occupied seat becomes empty if at least `tolerance` of its neighbours are.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rules {
+    pub neighbourhood: Neighbourhood,
+    pub tolerance: usize,
+}
+
+/// The seating area, stored densely in row-major order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Seating {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+
+const DIRECTIONS: [(i64, i64); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+impl FromProblemInput for Seating {
+    fn from(lines: &ProblemInput) -> Self {
+        let height = lines.lines.len();
+        let width = lines.lines.first().map_or(0, |line| line.len());
+        let cells: Vec<Cell> = lines
+            .lines
+            .iter()
+            .flat_map(|line| line.chars().map(Cell::from_char))
+            .collect();
+        assert_eq!(cells.len(), width * height, "rows must have equal length");
+
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+}
+
+impl Display for Seating {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for row in self.cells.chunks(self.width.max(1)) {
+            writeln!(f, "{}", row.iter().map(|c| c.to_char()).collect::<String>())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Seating {
+    pub fn occupied(&self) -> usize {
+        self.cells.iter().filter(|&&c| c == Cell::Occupied).count()
+    }
+
+    /// The index of the cell `(dx, dy)` away from `index`, if it is on the grid.
+    fn offset(&self, index: usize, (dx, dy): (i64, i64)) -> Option<usize> {
+        let x = (index % self.width) as i64 + dx;
+        let y = (index / self.width) as i64 + dy;
+
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            Some(y as usize * self.width + x as usize)
+        } else {
+            None
+        }
+    }
+
+    /// For each cell, the seats it considers.  These never change, so are computed once.
+    fn neighbours(&self, neighbourhood: Neighbourhood) -> Vec<Vec<usize>> {
+        (0..self.cells.len())
+            .map(|index| {
+                DIRECTIONS
+                    .iter()
+                    .filter_map(|&direction| {
+                        let mut current = self.offset(index, direction)?;
+
+                        if neighbourhood == Neighbourhood::LineOfSight {
+                            while self.cells[current] == Cell::Floor {
+                                current = self.offset(current, direction)?;
+                            }
+                        }
+
+                        Some(current).filter(|&c| self.cells[c] != Cell::Floor)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Applies `rules` to every seat simultaneously, returning whether anything changed.
+    fn step(&mut self, rules: &Rules, neighbours: &[Vec<usize>]) -> bool {
+        let next: Vec<Cell> = self
+            .cells
+            .iter()
+            .zip(neighbours)
+            .map(|(&cell, around)| {
+                let occupied = around
+                    .iter()
+                    .filter(|&&n| self.cells[n] == Cell::Occupied)
+                    .count();
+
+                match cell {
+                    Cell::Empty if occupied == 0 => Cell::Occupied,
+                    Cell::Occupied if occupied >= rules.tolerance => Cell::Empty,
+                    cell => cell,
+                }
+            })
+            .collect();
+
+        let changed = next != self.cells;
+        self.cells = next;
+
+        changed
+    }
+
+    /// Runs the rules until nothing changes, returning the stable seating and the number
+    /// of generations it took.  `on_frame` is called with every generation, starting
+    /// with the initial seating, which is useful for dumping frames while debugging.
+    pub fn simulate<F>(&self, rules: &Rules, mut on_frame: F) -> (Seating, usize)
+    where
+        F: FnMut(usize, &Seating),
+    {
+        let neighbours = self.neighbours(rules.neighbourhood);
+        let mut seating = self.clone();
+        let mut generation = 0;
+
+        on_frame(generation, &seating);
+        while seating.step(rules, &neighbours) {
+            generation += 1;
+            on_frame(generation, &seating);
+        }
+
+        (seating, generation)
+    }
+}
+
+fn stable_occupancy(lines: &ProblemInput, rules: Rules) -> String {
+    let (stable, _) = lines.parse::<Seating>().simulate(&rules, |_, _| {});
+    stable.occupied().to_string()
+}
+
 impl Solution for Q11 {
-    fn part1(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part1(&self, lines: &ProblemInput) -> String {
+        stable_occupancy(
+            lines,
+            Rules {
+                neighbourhood: Neighbourhood::Adjacent,
+                tolerance: 4,
+            },
+        )
     }
 
-    fn part2(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part2(&self, lines: &ProblemInput) -> String {
+        stable_occupancy(
+            lines,
+            Rules {
+                neighbourhood: Neighbourhood::LineOfSight,
+                tolerance: 5,
+            },
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::load_problem_input;
+
+    fn example() -> ProblemInput {
+        ProblemInput::from(vec![
+            "L.LL.LL.LL",
+            "LLLLLLL.LL",
+            "L.L.L..L..",
+            "LLLL.LL.LL",
+            "L.LL.LL.LL",
+            "L.LLLLL.LL",
+            "..L.L.....",
+            "LLLLLLLLLL",
+            "L.LLLLLL.L",
+            "L.LLLLL.LL",
+        ])
+    }
 
     #[test]
     fn test_part1_solution() {
         let q11 = Q11;
-        assert_eq!(q11.part1(&load_problem_input(1)), String::new());
+        assert_eq!(q11.part1(&example()), 37.to_string());
     }
 
     #[test]
     fn test_part2_solution() {
         let q11 = Q11;
-        assert_eq!(q11.part2(&load_problem_input(1)), String::new());
+        assert_eq!(q11.part2(&example()), 26.to_string());
+    }
+
+    #[test]
+    fn test_frames() {
+        let rules = Rules {
+            neighbourhood: Neighbourhood::Adjacent,
+            tolerance: 4,
+        };
+        let mut frames = Vec::new();
+        let (stable, generations) = example()
+            .parse::<Seating>()
+            .simulate(&rules, |_, seating| frames.push(seating.to_string()));
+
+        assert_eq!(generations, 5);
+        assert_eq!(frames.len(), 6);
+        assert_eq!(frames[0], example().lines.join("\n") + "\n");
+        assert_eq!(frames[5], stable.to_string());
     }
 }
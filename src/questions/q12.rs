@@ -1,31 +1,185 @@
-use crate::{ProblemInput, Solution};
+use crate::grid::{Direction, Grid, GridVisitor, Movement, NullVisitor, Position};
+use crate::{FromProblemInputLine, ProblemInput, Solution};
 
 pub struct Q12;
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    North,
+    South,
+    East,
+    West,
+    Left,
+    Right,
+    Forward,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Instruction {
+    pub action: Action,
+    pub value: i64,
+}
+
+impl FromProblemInputLine for Instruction {
+    fn from_line(line: &str) -> Self {
+        let action = match line.chars().next() {
+            Some('N') => Action::North,
+            Some('S') => Action::South,
+            Some('E') => Action::East,
+            Some('W') => Action::West,
+            Some('L') => Action::Left,
+            Some('R') => Action::Right,
+            Some('F') => Action::Forward,
+            _ => panic!("invalid instruction {}", line),
+        };
+        let value = line[1..].parse().expect("failed to parse");
+
+        Self { action, value }
+    }
+}
+
+impl Instruction {
+    /// The compass direction moved in, if this instruction is N, S, E or W.
+    fn direction(self) -> Option<Direction> {
+        match self.action {
+            Action::North => Some(Direction::Up),
+            Action::South => Some(Direction::Down),
+            Action::East => Some(Direction::Right),
+            Action::West => Some(Direction::Left),
+            _ => None,
+        }
+    }
+
+    /// The number of quarter turns anticlockwise made by an L or R instruction.
+    fn quarter_turns(self) -> usize {
+        let degrees = match self.action {
+            Action::Left => self.value,
+            Action::Right => -self.value,
+            _ => 0,
+        };
+        assert_eq!(degrees % 90, 0, "can only turn in multiples of 90 degrees");
+
+        (degrees / 90).rem_euclid(4) as usize
+    }
+}
+
+/// How the ship interprets its instructions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Navigation {
+    /// N, S, E and W move the ship, L and R turn it and F moves it forward.
+    Heading,
+    /// N, S, E and W move a waypoint relative to the ship, L and R rotate the
+    /// waypoint around the ship and F moves the ship to the waypoint repeatedly.
+    Waypoint,
+}
+
+/// Moves the ship by `delta`, one unit step at a time so `grid`'s visitor sees
+/// every position passed through.
+fn go_by(grid: &mut Grid<impl Sized>, delta: Position) {
+    let horizontal = if delta.x < 0 {
+        Direction::Left
+    } else {
+        Direction::Right
+    };
+    let vertical = if delta.y < 0 {
+        Direction::Down
+    } else {
+        Direction::Up
+    };
+
+    grid.go(Movement::new(horizontal, delta.x.unsigned_abs() as usize));
+    grid.go(Movement::new(vertical, delta.y.unsigned_abs() as usize));
+}
+
+/// Follows `instructions` from the origin, returning the ship's final position along
+/// with the output of `visitor`, which sees the ship's whole trajectory.
+pub fn navigate<T, V>(
+    instructions: &[Instruction],
+    navigation: Navigation,
+    visitor: V,
+) -> (Position, T)
+where
+    V: 'static + GridVisitor<Output = T>,
+{
+    let mut grid = Grid::new(visitor);
+    let mut heading = Direction::Right;
+    let mut waypoint = Position::new(10, 1);
+
+    for &instruction in instructions {
+        match (navigation, instruction.direction()) {
+            (Navigation::Heading, Some(direction)) => {
+                grid.go(Movement::new(direction, instruction.value as usize));
+            }
+            (Navigation::Waypoint, Some(direction)) => {
+                for _ in 0..instruction.value {
+                    waypoint = waypoint.go(direction);
+                }
+            }
+            (_, None) if instruction.action == Action::Forward => match navigation {
+                Navigation::Heading => grid.go(Movement::new(heading, instruction.value as usize)),
+                Navigation::Waypoint => {
+                    for _ in 0..instruction.value {
+                        go_by(&mut grid, waypoint);
+                    }
+                }
+            },
+            (_, None) => {
+                for _ in 0..instruction.quarter_turns() {
+                    heading = heading.left();
+                    waypoint = waypoint.rotate_left();
+                }
+            }
+        }
+    }
+
+    (grid.pos, grid.visitor.process())
+}
+
+fn distance(lines: &ProblemInput, navigation: Navigation) -> String {
+    let instructions = lines.parse::<Vec<Instruction>>();
+    let (position, _) = navigate(&instructions, navigation, NullVisitor);
+
+    position.l1().to_string()
+}
+
 impl Solution for Q12 {
-    fn part1(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part1(&self, lines: &ProblemInput) -> String {
+        distance(lines, Navigation::Heading)
     }
 
-    fn part2(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part2(&self, lines: &ProblemInput) -> String {
+        distance(lines, Navigation::Waypoint)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::load_problem_input;
+    use crate::grid::StepVisitor;
+
+    fn example() -> ProblemInput {
+        ProblemInput::from(vec!["F10", "N3", "F7", "R90", "F11"])
+    }
 
     #[test]
     fn test_part1_solution() {
         let q12 = Q12;
-        assert_eq!(q12.part1(&load_problem_input(1)), String::new());
+        assert_eq!(q12.part1(&example()), 25.to_string());
     }
 
     #[test]
     fn test_part2_solution() {
         let q12 = Q12;
-        assert_eq!(q12.part2(&load_problem_input(1)), String::new());
+        assert_eq!(q12.part2(&example()), 286.to_string());
+    }
+
+    #[test]
+    fn test_trajectory() {
+        let instructions = example().parse::<Vec<Instruction>>();
+        let (position, steps) = navigate(&instructions, Navigation::Heading, StepVisitor::new());
+
+        assert_eq!(position, Position::new(17, -8));
+        assert_eq!(steps.len(), 31);
+        assert_eq!(steps[&position], 31);
     }
 }
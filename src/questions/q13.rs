@@ -1,31 +1,65 @@
+use crate::number_theory::crt;
 use crate::{ProblemInput, Solution};
 
 pub struct Q13;
 
+/// The earliest departure time, and each bus id along with its offset in the list.
+fn parse_notes(lines: &ProblemInput) -> (i128, Vec<(i128, i128)>) {
+    let earliest = lines.lines[0].parse().expect("failed to parse");
+    let buses = lines.lines[1]
+        .split(',')
+        .enumerate()
+        .filter(|&(_, id)| id != "x")
+        .map(|(offset, id)| (offset as i128, id.parse().expect("failed to parse")))
+        .collect();
+
+    (earliest, buses)
+}
+
 impl Solution for Q13 {
-    fn part1(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part1(&self, lines: &ProblemInput) -> String {
+        let (earliest, buses) = parse_notes(lines);
+
+        let (wait, id) = buses
+            .into_iter()
+            .map(|(_, id)| ((id - earliest % id) % id, id))
+            .min()
+            .unwrap();
+
+        (wait * id).to_string()
     }
 
-    fn part2(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part2(&self, lines: &ProblemInput) -> String {
+        let (_, buses) = parse_notes(lines);
+
+        // Bus `id` at `offset` departs at t + offset, i.e. t = -offset (mod id).
+        let congruences: Vec<_> = buses
+            .into_iter()
+            .map(|(offset, id)| (-offset, id))
+            .collect();
+        let (t, _) = crt(&congruences).unwrap();
+
+        t.to_string()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::load_problem_input;
+
+    fn example() -> ProblemInput {
+        ProblemInput::from(vec!["939", "7,13,x,x,59,x,31,19"])
+    }
 
     #[test]
     fn test_part1_solution() {
         let q13 = Q13;
-        assert_eq!(q13.part1(&load_problem_input(1)), String::new());
+        assert_eq!(q13.part1(&example()), 295.to_string());
     }
 
     #[test]
     fn test_part2_solution() {
         let q13 = Q13;
-        assert_eq!(q13.part2(&load_problem_input(1)), String::new());
+        assert_eq!(q13.part2(&example()), 1068781.to_string());
     }
 }
@@ -1,31 +1,346 @@
-use crate::{ProblemInput, Solution};
+use crate::{FromProblemInputLine, ProblemInput, Solution};
 
 pub struct Q14;
 
+/// The width of the emulator's values and addresses.
+const BITS: u32 = 36;
+const ALL_BITS: u64 = (1 << BITS) - 1;
+
+/// A bitmask, as given by a `mask = ...` line, split into the positions holding each
+/// of `1`, `0` and `X`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Mask {
+    pub ones: u64,
+    pub zeros: u64,
+    pub floating: u64,
+}
+
+impl FromProblemInputLine for Mask {
+    fn from_line(line: &str) -> Self {
+        assert_eq!(line.len(), BITS as usize, "invalid mask {}", line);
+
+        let mut mask = Self {
+            ones: 0,
+            zeros: 0,
+            floating: 0,
+        };
+
+        for (i, c) in line.chars().rev().enumerate() {
+            let bit = 1 << i;
+            match c {
+                '1' => mask.ones |= bit,
+                '0' => mask.zeros |= bit,
+                'X' => mask.floating |= bit,
+                c => panic!("invalid mask bit {}", c),
+            }
+        }
+
+        mask
+    }
+}
+
+impl Mask {
+    /// The mask which leaves writes unchanged under `version`, used before any mask is
+    /// set: all `X` for version 1, but all `0` for version 2, where `X` means floating.
+    pub fn identity(version: Version) -> Self {
+        match version {
+            Version::V1 => Self {
+                ones: 0,
+                zeros: 0,
+                floating: ALL_BITS,
+            },
+            Version::V2 => Self {
+                ones: 0,
+                zeros: ALL_BITS,
+                floating: 0,
+            },
+        }
+    }
+
+    /// Version 1: `0` and `1` overwrite the bits of the value, `X` leaves them alone.
+    pub fn apply_value(self, value: u64) -> u64 {
+        (value & self.floating) | self.ones
+    }
+
+    /// Version 2: `1` overwrites the bits of the address, `0` leaves them alone and `X`
+    /// makes them take every possible value.
+    pub fn decode_address(self, address: u64) -> AddressPattern {
+        AddressPattern::new(address | self.ones, self.floating)
+    }
+}
+
+/// A set of addresses, namely those which agree with `value` on every bit which is
+/// not `floating`.  This represents the `2^n` addresses written by a version 2
+/// instruction without listing them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AddressPattern {
+    value: u64,
+    floating: u64,
+}
+
+impl AddressPattern {
+    pub fn new(value: u64, floating: u64) -> Self {
+        Self {
+            value: value & !floating,
+            floating,
+        }
+    }
+
+    /// The pattern matching only `address`.
+    pub fn single(address: u64) -> Self {
+        Self::new(address, 0)
+    }
+
+    /// The number of addresses in the pattern.
+    pub fn count(self) -> u64 {
+        1 << self.floating.count_ones()
+    }
+
+    pub fn contains(self, address: u64) -> bool {
+        address & !self.floating == self.value
+    }
+
+    pub fn intersects(self, other: Self) -> bool {
+        let fixed = !self.floating & !other.floating;
+        (self.value ^ other.value) & fixed == 0
+    }
+
+    /// The addresses in `self` but not `other`, as disjoint patterns.  Each floating bit
+    /// of `self` which `other` fixes splits off the half of `self` that disagrees with
+    /// `other` on that bit, so there are at most as many pieces as floating bits.
+    pub fn subtract(self, other: Self) -> Vec<Self> {
+        if !self.intersects(other) {
+            return vec![self];
+        }
+
+        let mut pieces = Vec::new();
+        let mut rest = self;
+        let mut splits = self.floating & !other.floating;
+
+        while splits != 0 {
+            let bit = splits & splits.wrapping_neg();
+            splits &= !bit;
+
+            let floating = rest.floating & !bit;
+            pieces.push(Self::new(rest.value | (!other.value & bit), floating));
+            rest = Self::new(rest.value | (other.value & bit), floating);
+        }
+
+        pieces
+    }
+
+    /// Every address in the pattern, in increasing order.
+    pub fn addresses(self) -> impl Iterator<Item = u64> {
+        // Step through the subsets of `floating` by counting within its bits only.
+        let mut subset = Some(0_u64);
+
+        std::iter::from_fn(move || {
+            let current = subset?;
+            let next = current.wrapping_sub(self.floating) & self.floating;
+            subset = if next == 0 { None } else { Some(next) };
+
+            Some(self.value | current)
+        })
+    }
+}
+
+/// Memory stored as a list of disjoint address patterns, each holding a single value.
+/// Unwritten addresses hold zero.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Memory {
+    regions: Vec<(AddressPattern, u64)>,
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes `value` to every address in `pattern`, carving those addresses out of any
+    /// earlier regions.
+    pub fn write(&mut self, pattern: AddressPattern, value: u64) {
+        self.regions = self
+            .regions
+            .iter()
+            .flat_map(|&(region, v)| region.subtract(pattern).into_iter().map(move |r| (r, v)))
+            .collect();
+
+        if value != 0 {
+            self.regions.push((pattern, value));
+        }
+    }
+
+    pub fn read(&self, address: u64) -> u64 {
+        self.regions
+            .iter()
+            .find(|(region, _)| region.contains(address))
+            .map_or(0, |&(_, value)| value)
+    }
+
+    /// The sum of every value in memory.
+    pub fn sum(&self) -> u128 {
+        self.regions
+            .iter()
+            .map(|&(region, value)| region.count() as u128 * value as u128)
+            .sum()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    SetMask(Mask),
+    Write { address: u64, value: u64 },
+}
+
+impl FromProblemInputLine for Instruction {
+    fn from_line(line: &str) -> Self {
+        let (target, value) = {
+            let mut parts = line.splitn(2, " = ");
+            (parts.next().unwrap(), parts.next().expect("missing value"))
+        };
+
+        if target == "mask" {
+            Instruction::SetMask(Mask::from_line(value))
+        } else {
+            let address = target
+                .trim_start_matches("mem[")
+                .trim_end_matches(']')
+                .parse()
+                .expect("failed to parse");
+            let value = value.parse().expect("failed to parse");
+
+            Instruction::Write { address, value }
+        }
+    }
+}
+
+/// How the decoder chip interprets the mask.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Version {
+    /// The mask modifies values written to memory.
+    V1,
+    /// The mask decodes the address written to into a set of floating addresses.
+    V2,
+}
+
+pub struct Emulator {
+    version: Version,
+    mask: Mask,
+    pub memory: Memory,
+}
+
+impl Emulator {
+    pub fn new(version: Version) -> Self {
+        Self {
+            version,
+            mask: Mask::identity(version),
+            memory: Memory::new(),
+        }
+    }
+
+    pub fn execute(&mut self, instruction: Instruction) {
+        match instruction {
+            Instruction::SetMask(mask) => self.mask = mask,
+            Instruction::Write { address, value } => match self.version {
+                Version::V1 => self.memory.write(
+                    AddressPattern::single(address),
+                    self.mask.apply_value(value),
+                ),
+                Version::V2 => self.memory.write(self.mask.decode_address(address), value),
+            },
+        }
+    }
+
+    /// Runs `instructions` on a fresh emulator, returning the final memory.
+    pub fn run(version: Version, instructions: &[Instruction]) -> Memory {
+        let mut emulator = Self::new(version);
+        for &instruction in instructions {
+            emulator.execute(instruction);
+        }
+
+        emulator.memory
+    }
+}
+
+fn memory_sum(lines: &ProblemInput, version: Version) -> String {
+    let instructions = lines.parse::<Vec<Instruction>>();
+    Emulator::run(version, &instructions).sum().to_string()
+}
+
 impl Solution for Q14 {
-    fn part1(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part1(&self, lines: &ProblemInput) -> String {
+        memory_sum(lines, Version::V1)
     }
 
-    fn part2(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part2(&self, lines: &ProblemInput) -> String {
+        memory_sum(lines, Version::V2)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::load_problem_input;
+
+    fn example_v1() -> ProblemInput {
+        ProblemInput::from(vec![
+            "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X",
+            "mem[8] = 11",
+            "mem[7] = 101",
+            "mem[8] = 0",
+        ])
+    }
+
+    fn example_v2() -> ProblemInput {
+        ProblemInput::from(vec![
+            "mask = 000000000000000000000000000000X1001X",
+            "mem[42] = 100",
+            "mask = 00000000000000000000000000000000X0XX",
+            "mem[26] = 1",
+        ])
+    }
 
     #[test]
     fn test_part1_solution() {
         let q14 = Q14;
-        assert_eq!(q14.part1(&load_problem_input(1)), String::new());
+        assert_eq!(q14.part1(&example_v1()), 165.to_string());
     }
 
     #[test]
     fn test_part2_solution() {
         let q14 = Q14;
-        assert_eq!(q14.part2(&load_problem_input(1)), String::new());
+        assert_eq!(q14.part2(&example_v2()), 208.to_string());
+    }
+
+    #[test]
+    fn test_floating_addresses() {
+        let mask = Mask::from_line("000000000000000000000000000000X1001X");
+        let pattern = mask.decode_address(42);
+        assert_eq!(
+            pattern.addresses().collect::<Vec<_>>(),
+            vec![26, 27, 58, 59]
+        );
+
+        let pieces = pattern.subtract(AddressPattern::new(16, 0b1011));
+        let remaining: Vec<_> = pieces.iter().flat_map(|p| p.addresses()).collect();
+        assert_eq!(
+            remaining.len() as u64,
+            pieces.iter().map(|p| p.count()).sum::<u64>()
+        );
+        assert!(remaining.iter().all(|&a| a == 58 || a == 59));
+
+        // 34 floating bits would be 2^34 addresses if stored individually.
+        let instructions = example_v1().parse::<Vec<Instruction>>();
+        let memory = Emulator::run(Version::V2, &instructions);
+        assert_eq!(memory.sum(), 101 << 34);
+        assert_eq!(memory.read(7 | 1 << 6), 101);
+        assert_eq!(memory.read(8 | 1 << 6), 0);
+
+        // Before any mask is set, writes go to exactly the address given.
+        for &version in [Version::V1, Version::V2].iter() {
+            let write = Instruction::from_line("mem[8] = 11");
+            let memory = Emulator::run(version, &[write]);
+            assert_eq!(memory.sum(), 11);
+            assert_eq!(memory.read(8), 11);
+        }
     }
 }
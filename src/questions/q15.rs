@@ -2,30 +2,123 @@ use crate::{ProblemInput, Solution};
 
 pub struct Q15;
 
+/// The numbers spoken in the elves' memory game: after the starting numbers, each
+/// turn speaks how many turns ago the previous number was last spoken before that,
+/// or 0 if it was new.
+pub struct MemoryGame {
+    starting: Vec<u32>,
+    /// The turn (counting from 1) on which each number was last spoken, or 0 if never.
+    last_seen: Vec<u32>,
+    turn: u32,
+    next: u32,
+}
+
+impl MemoryGame {
+    pub fn new(starting: &[u32]) -> Self {
+        Self::with_capacity(starting, 0)
+    }
+
+    /// A game whose last-seen table is sized up front for `turns` turns.  No number
+    /// spoken in the first `turns` turns can be larger than `turns`, so the table then
+    /// never needs to grow.
+    pub fn with_capacity(starting: &[u32], turns: usize) -> Self {
+        let largest = starting.iter().max().map_or(0, |&n| n as usize + 1);
+
+        Self {
+            starting: starting.to_vec(),
+            last_seen: vec![0; largest.max(turns)],
+            turn: 0,
+            next: 0,
+        }
+    }
+}
+
+impl Iterator for MemoryGame {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        let spoken = self
+            .starting
+            .get(self.turn as usize)
+            .copied()
+            .unwrap_or(self.next);
+        self.turn += 1;
+
+        if spoken as usize >= self.last_seen.len() {
+            self.last_seen.resize(spoken as usize + 1, 0);
+        }
+        let previous = std::mem::replace(&mut self.last_seen[spoken as usize], self.turn);
+        self.next = if previous == 0 {
+            0
+        } else {
+            self.turn - previous
+        };
+
+        Some(spoken)
+    }
+}
+
+/// The number spoken on turn `turn`, counting from 1, or `None` for turn 0.
+pub fn nth_spoken(starting: &[u32], turn: usize) -> Option<u32> {
+    MemoryGame::with_capacity(starting, turn).nth(turn.checked_sub(1)?)
+}
+
+fn starting_numbers(lines: &ProblemInput) -> Vec<u32> {
+    lines
+        .as_csv()
+        .iter()
+        .map(|n| n.trim().parse().expect("failed to parse"))
+        .collect()
+}
+
 impl Solution for Q15 {
-    fn part1(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part1(&self, lines: &ProblemInput) -> String {
+        nth_spoken(&starting_numbers(lines), 2020)
+            .unwrap()
+            .to_string()
     }
 
-    fn part2(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part2(&self, lines: &ProblemInput) -> String {
+        nth_spoken(&starting_numbers(lines), 30_000_000)
+            .unwrap()
+            .to_string()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::load_problem_input;
 
     #[test]
     fn test_part1_solution() {
         let q15 = Q15;
-        assert_eq!(q15.part1(&load_problem_input(1)), String::new());
+        assert_eq!(
+            q15.part1(&ProblemInput::from(vec!["0,3,6"])),
+            436.to_string()
+        );
+        assert_eq!(q15.part1(&ProblemInput::from(vec!["1,3,2"])), 1.to_string());
+        assert_eq!(
+            q15.part1(&ProblemInput::from(vec!["3,1,2"])),
+            1836.to_string()
+        );
     }
 
     #[test]
     fn test_part2_solution() {
         let q15 = Q15;
-        assert_eq!(q15.part2(&load_problem_input(1)), String::new());
+        assert_eq!(
+            q15.part2(&ProblemInput::from(vec!["0,3,6"])),
+            175594.to_string()
+        );
+    }
+
+    #[test]
+    fn test_sequence() {
+        let spoken: Vec<_> = MemoryGame::new(&[0, 3, 6]).take(10).collect();
+        assert_eq!(spoken, vec![0, 3, 6, 0, 3, 3, 1, 0, 4, 0]);
+        assert_eq!(MemoryGame::new(&[2, 1, 3]).nth(2019), Some(10));
+        assert_eq!(nth_spoken(&[0, 3, 6], 1), Some(0));
+        assert_eq!(nth_spoken(&[0, 3, 6], 4), Some(0));
+        assert_eq!(nth_spoken(&[0, 3, 6], 0), None);
     }
 }
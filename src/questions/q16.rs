@@ -1,31 +1,365 @@
-use crate::{ProblemInput, Solution};
+use crate::{FromProblemInput, FromProblemInputLine, ProblemInput, Solution};
+use anyhow::{bail, Result};
+use std::ops::RangeInclusive;
 
 pub struct Q16;
 
+/// A ticket field, valid for values in either of its two ranges.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldRule {
+    pub name: String,
+    pub ranges: [RangeInclusive<u32>; 2],
+}
+
+impl FromProblemInputLine for FieldRule {
+    fn from_line(line: &str) -> Self {
+        fn range(s: &str) -> RangeInclusive<u32> {
+            let mut bounds = s
+                .trim()
+                .splitn(2, '-')
+                .map(|b| b.parse().expect("failed to parse"));
+            bounds.next().unwrap()..=bounds.next().expect("missing upper bound")
+        }
+
+        let colon = line.find(':').expect("missing field name");
+        let mut ranges = line[colon + 1..].splitn(2, " or ").map(range);
+
+        Self {
+            name: line[..colon].to_string(),
+            ranges: [
+                ranges.next().unwrap(),
+                ranges.next().expect("missing second range"),
+            ],
+        }
+    }
+}
+
+impl FieldRule {
+    pub fn allows(&self, value: u32) -> bool {
+        self.ranges.iter().any(|r| r.contains(&value))
+    }
+}
+
+pub type Ticket = Vec<u32>;
+
+/// The three sections of the notes: the field rules, your ticket and nearby tickets.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Notes {
+    pub rules: Vec<FieldRule>,
+    pub yours: Ticket,
+    pub nearby: Vec<Ticket>,
+}
+
+impl FromProblemInput for Notes {
+    fn from(lines: &ProblemInput) -> Self {
+        fn ticket(line: &str) -> Ticket {
+            line.split(',')
+                .map(|v| v.parse().expect("failed to parse"))
+                .collect()
+        }
+
+        let sections: Vec<_> = lines.lines.split(String::is_empty).collect();
+        assert_eq!(
+            sections.len(),
+            3,
+            "expected rules, your ticket and nearby tickets"
+        );
+
+        Self {
+            rules: sections[0]
+                .iter()
+                .map(|l| FieldRule::from_line(l))
+                .collect(),
+            yours: ticket(&sections[1][1]),
+            nearby: sections[2][1..].iter().map(|l| ticket(l)).collect(),
+        }
+    }
+}
+
+impl Notes {
+    /// The values on `ticket` which are not valid for any field.
+    pub fn invalid_values<'a>(&'a self, ticket: &'a [u32]) -> impl Iterator<Item = u32> + 'a {
+        ticket
+            .iter()
+            .copied()
+            .filter(move |&v| !self.rules.iter().any(|rule| rule.allows(v)))
+    }
+
+    pub fn scanning_error_rate(&self) -> u32 {
+        self.nearby
+            .iter()
+            .flat_map(|ticket| self.invalid_values(ticket))
+            .sum()
+    }
+
+    /// The nearby tickets with no invalid values, along with your own.
+    pub fn valid_tickets(&self) -> Vec<&Ticket> {
+        std::iter::once(&self.yours)
+            .chain(
+                self.nearby
+                    .iter()
+                    .filter(|ticket| self.invalid_values(ticket).next().is_none()),
+            )
+            .collect()
+    }
+
+    /// For each field, a bitmask of the positions every valid ticket allows it in.
+    fn candidates(&self) -> Result<Vec<u64>> {
+        let positions = self.yours.len();
+        if positions > 64 {
+            bail!("at most 64 ticket positions are supported");
+        }
+        if let Some(i) = self.nearby.iter().position(|t| t.len() != positions) {
+            bail!(
+                "nearby ticket {} has {} values, expected {}",
+                i + 1,
+                self.nearby[i].len(),
+                positions
+            );
+        }
+
+        let tickets = self.valid_tickets();
+        Ok(self
+            .rules
+            .iter()
+            .map(|rule| {
+                (0..positions)
+                    .filter(|&p| tickets.iter().all(|ticket| rule.allows(ticket[p])))
+                    .fold(0, |mask, p| mask | 1 << p)
+            })
+            .collect())
+    }
+
+    /// The position of each field on a ticket, indexed like `rules`.  Returns an error
+    /// naming the undecided fields if the tickets allow more than one assignment, or if
+    /// they allow none.
+    pub fn resolve(&self) -> Result<Vec<usize>> {
+        let mut candidates = self.candidates()?;
+        propagate(&mut candidates, self.rules.len() == self.yours.len());
+
+        let mut solutions = Vec::new();
+        search(
+            &candidates,
+            &mut vec![None; candidates.len()],
+            0,
+            &mut solutions,
+        );
+
+        match solutions.len() {
+            0 => bail!("no assignment of fields to positions is consistent with the tickets"),
+            1 => Ok(solutions.pop().unwrap()),
+            _ => {
+                let undecided: Vec<_> = self
+                    .rules
+                    .iter()
+                    .zip(&candidates)
+                    .filter(|(_, mask)| mask.count_ones() > 1)
+                    .map(|(rule, mask)| format!("{} in {:?}", rule.name, positions(*mask)))
+                    .collect();
+                bail!("ambiguous field positions: {}", undecided.join(", "))
+            }
+        }
+    }
+}
+
+fn positions(mask: u64) -> Vec<usize> {
+    (0..64).filter(|p| mask & 1 << p != 0).collect()
+}
+
+/// Repeatedly fixes fields with only one candidate position, removing it from every
+/// other field.  If every position must be filled, `exact`, positions with only one
+/// candidate field are fixed to that field too.
+fn propagate(candidates: &mut [u64], exact: bool) {
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+
+        for field in 0..candidates.len() {
+            let mask = candidates[field];
+            if mask.count_ones() != 1 {
+                continue;
+            }
+            for (other, c) in candidates.iter_mut().enumerate() {
+                if other != field && *c & mask != 0 {
+                    *c &= !mask;
+                    changed = true;
+                }
+            }
+        }
+
+        if !exact {
+            continue;
+        }
+
+        let all = candidates.iter().fold(0, |acc, &c| acc | c);
+        for p in positions(all) {
+            let bit = 1 << p;
+            let mut holders = candidates.iter_mut().filter(|c| **c & bit != 0);
+            let only = holders.next();
+            if let (Some(c), None) = (only, holders.next()) {
+                if *c != bit {
+                    *c = bit;
+                    changed = true;
+                }
+            }
+        }
+    }
+}
+
+/// Extends `assigned` to assignments of distinct positions to every field, stopping once
+/// two are found since that is enough to show the answer is ambiguous.
+fn search(
+    candidates: &[u64],
+    assigned: &mut [Option<usize>],
+    used: u64,
+    solutions: &mut Vec<Vec<usize>>,
+) {
+    if solutions.len() > 1 {
+        return;
+    }
+
+    // Branch on the unassigned field with the fewest remaining choices.
+    let next = (0..candidates.len())
+        .filter(|&f| assigned[f].is_none())
+        .min_by_key(|&f| (candidates[f] & !used).count_ones());
+
+    let field = match next {
+        Some(field) => field,
+        None => {
+            solutions.push(assigned.iter().map(|p| p.unwrap()).collect());
+            return;
+        }
+    };
+
+    for p in positions(candidates[field] & !used) {
+        assigned[field] = Some(p);
+        search(candidates, assigned, used | 1 << p, solutions);
+        assigned[field] = None;
+    }
+}
+
 impl Solution for Q16 {
-    fn part1(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part1(&self, lines: &ProblemInput) -> String {
+        lines.parse::<Notes>().scanning_error_rate().to_string()
     }
 
-    fn part2(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part2(&self, lines: &ProblemInput) -> String {
+        let notes = lines.parse::<Notes>();
+        let positions = notes.resolve().unwrap();
+
+        notes
+            .rules
+            .iter()
+            .zip(positions)
+            .filter(|(rule, _)| rule.name.starts_with("departure"))
+            .map(|(_, p)| notes.yours[p] as u64)
+            .product::<u64>()
+            .to_string()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::load_problem_input;
 
     #[test]
     fn test_part1_solution() {
         let q16 = Q16;
-        assert_eq!(q16.part1(&load_problem_input(1)), String::new());
+        let lines = ProblemInput::from(vec![
+            "class: 1-3 or 5-7",
+            "row: 6-11 or 33-44",
+            "seat: 13-40 or 45-50",
+            "",
+            "your ticket:",
+            "7,1,14",
+            "",
+            "nearby tickets:",
+            "7,3,47",
+            "40,4,50",
+            "55,2,20",
+            "38,6,12",
+        ]);
+        assert_eq!(q16.part1(&lines), 71.to_string());
+    }
+
+    fn resolution_example() -> ProblemInput {
+        ProblemInput::from(vec![
+            "departure class: 0-1 or 4-19",
+            "row: 0-5 or 8-19",
+            "departure seat: 0-13 or 16-19",
+            "",
+            "your ticket:",
+            "11,12,13",
+            "",
+            "nearby tickets:",
+            "3,9,18",
+            "15,1,5",
+            "5,14,9",
+        ])
     }
 
     #[test]
     fn test_part2_solution() {
         let q16 = Q16;
-        assert_eq!(q16.part2(&load_problem_input(1)), String::new());
+        assert_eq!(q16.part2(&resolution_example()), (12 * 13).to_string());
+    }
+
+    #[test]
+    fn test_resolution() {
+        let notes = resolution_example().parse::<Notes>();
+        assert_eq!(notes.resolve().unwrap(), vec![1, 0, 2]);
+
+        let ambiguous = ProblemInput::from(vec![
+            "a: 0-5 or 10-10",
+            "b: 0-5 or 10-10",
+            "",
+            "your ticket:",
+            "1,2",
+            "",
+            "nearby tickets:",
+        ])
+        .parse::<Notes>();
+        let error = ambiguous.resolve().unwrap_err().to_string();
+        assert_eq!(error, "ambiguous field positions: a in [0, 1], b in [0, 1]");
+
+        let impossible = ProblemInput::from(vec![
+            "a: 0-5 or 10-10",
+            "b: 0-5 or 10-10",
+            "",
+            "your ticket:",
+            "1,7",
+            "",
+            "nearby tickets:",
+        ])
+        .parse::<Notes>();
+        assert!(impossible.resolve().is_err());
+
+        let spare_position = ProblemInput::from(vec![
+            "a: 0-5 or 10-10",
+            "",
+            "your ticket:",
+            "1,2",
+            "",
+            "nearby tickets:",
+        ])
+        .parse::<Notes>();
+        let error = spare_position.resolve().unwrap_err().to_string();
+        assert_eq!(error, "ambiguous field positions: a in [0, 1]");
+
+        let short_ticket = ProblemInput::from(vec![
+            "a: 0-5 or 10-10",
+            "b: 0-5 or 10-10",
+            "",
+            "your ticket:",
+            "1,2",
+            "",
+            "nearby tickets:",
+            "3,4",
+            "5",
+        ])
+        .parse::<Notes>();
+        let error = short_ticket.resolve().unwrap_err().to_string();
+        assert_eq!(error, "nearby ticket 2 has 1 values, expected 2");
     }
 }
@@ -1,31 +1,185 @@
 use crate::{ProblemInput, Solution};
+use std::collections::HashSet;
 
 pub struct Q17;
 
+/// A cube's coordinates; the first two lie in the plane of the initial slice.
+pub type Cube = Vec<i32>;
+
+/// An infinite grid of cubes in any number of dimensions, storing only the active ones.
+///
+/// The initial slice lies in the plane where every coordinate after the first two is
+/// zero, so the state stays symmetric under negating any of those extra coordinates and
+/// under permuting them.  Only one representative of each class of symmetric cubes is
+/// stored and simulated: the one whose extra coordinates are non-negative and sorted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PocketDimension {
+    dimensions: usize,
+    active: HashSet<Cube>,
+}
+
+impl PocketDimension {
+    /// The pocket dimension whose active cubes are the `#`s of `slice`.
+    pub fn from_slice(slice: &ProblemInput, dimensions: usize) -> Self {
+        assert!(dimensions >= 2, "the initial slice needs two dimensions");
+
+        let active = slice
+            .lines
+            .iter()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == '#')
+                    .map(move |(x, _)| {
+                        let mut cube = vec![0; dimensions];
+                        cube[0] = x as i32;
+                        cube[1] = y as i32;
+                        cube
+                    })
+            })
+            .collect();
+
+        Self { dimensions, active }
+    }
+
+    /// The representative of `cube` under the symmetries of the extra coordinates.
+    fn canonical(mut cube: Cube) -> Cube {
+        for c in &mut cube[2..] {
+            *c = c.abs();
+        }
+        cube[2..].sort_unstable();
+
+        cube
+    }
+
+    /// How many cubes `cube`, a representative, stands for: the distinct arrangements
+    /// of its extra coordinates, times two for each non-zero one.
+    fn orbit_size(cube: &[i32]) -> usize {
+        let extra = &cube[2..];
+        let mut size = (1..=extra.len()).product::<usize>();
+
+        // `extra` is sorted, so equal coordinates are adjacent.
+        let mut run = 1;
+        for (i, c) in extra.iter().enumerate().skip(1) {
+            run = if *c == extra[i - 1] { run + 1 } else { 1 };
+            size /= run;
+        }
+
+        size << extra.iter().filter(|&&c| c != 0).count()
+    }
+
+    /// Every cube adjacent to `cube`, including diagonally.
+    fn neighbours(cube: &[i32]) -> impl Iterator<Item = Cube> + '_ {
+        let count = 3_usize.pow(cube.len() as u32);
+
+        (0..count)
+            .filter(move |&i| i != count / 2)
+            .map(move |mut i| {
+                cube.iter()
+                    .map(|&c| {
+                        let offset = (i % 3) as i32 - 1;
+                        i /= 3;
+                        c + offset
+                    })
+                    .collect()
+            })
+    }
+
+    pub fn is_active(&self, cube: &[i32]) -> bool {
+        cube.len() == self.dimensions && self.active.contains(&Self::canonical(cube.to_vec()))
+    }
+
+    /// The number of active cubes.
+    pub fn active(&self) -> usize {
+        self.active.iter().map(|cube| Self::orbit_size(cube)).sum()
+    }
+
+    /// Runs one cycle: an active cube stays active with two or three active neighbours,
+    /// and an inactive cube becomes active with exactly three.
+    pub fn step(&self) -> Self {
+        // Any cube which could change is active or next to an active cube, and if the
+        // symmetric copy of a cube is next to an active cube then so is the cube itself.
+        let candidates: HashSet<Cube> = self
+            .active
+            .iter()
+            .flat_map(|cube| Self::neighbours(cube).map(Self::canonical))
+            .chain(self.active.iter().cloned())
+            .collect();
+
+        let active = candidates
+            .into_iter()
+            .filter(|cube| {
+                let around = Self::neighbours(cube)
+                    .filter(|n| self.active.contains(&Self::canonical(n.clone())))
+                    .count();
+
+                around == 3 || (around == 2 && self.active.contains(cube))
+            })
+            .collect();
+
+        Self {
+            dimensions: self.dimensions,
+            active,
+        }
+    }
+
+    /// The state after `cycles` cycles.
+    pub fn simulate(&self, cycles: usize) -> Self {
+        (0..cycles).fold(self.clone(), |state, _| state.step())
+    }
+}
+
+fn active_after_boot(lines: &ProblemInput, dimensions: usize) -> String {
+    PocketDimension::from_slice(lines, dimensions)
+        .simulate(6)
+        .active()
+        .to_string()
+}
+
 impl Solution for Q17 {
-    fn part1(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part1(&self, lines: &ProblemInput) -> String {
+        active_after_boot(lines, 3)
     }
 
-    fn part2(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part2(&self, lines: &ProblemInput) -> String {
+        active_after_boot(lines, 4)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::load_problem_input;
+
+    fn example() -> ProblemInput {
+        ProblemInput::from(vec![".#.", "..#", "###"])
+    }
 
     #[test]
     fn test_part1_solution() {
         let q17 = Q17;
-        assert_eq!(q17.part1(&load_problem_input(1)), String::new());
+        assert_eq!(q17.part1(&example()), 112.to_string());
     }
 
     #[test]
     fn test_part2_solution() {
         let q17 = Q17;
-        assert_eq!(q17.part2(&load_problem_input(1)), String::new());
+        assert_eq!(q17.part2(&example()), 848.to_string());
+    }
+
+    #[test]
+    fn test_symmetry() {
+        let three = PocketDimension::from_slice(&example(), 3).step();
+        assert_eq!(three.active(), 11);
+        assert!(three.is_active(&[0, 1, -1]));
+        assert!(three.is_active(&[0, 1, 1]));
+        assert!(!three.is_active(&[1, 1, 1]));
+
+        assert_eq!(
+            PocketDimension::from_slice(&example(), 4).step().active(),
+            29
+        );
+        assert_eq!(PocketDimension::orbit_size(&[0, 0, 1, 1, 2]), 3 * 8);
+        assert_eq!(PocketDimension::orbit_size(&[0, 0, 0, 0]), 1);
     }
 }
@@ -1,31 +1,61 @@
+use crate::expression::{parse, Associativity, Operator, OperatorTable};
 use crate::{ProblemInput, Solution};
 
 pub struct Q18;
 
+fn sum_of_results(lines: &ProblemInput, table: &OperatorTable) -> String {
+    lines
+        .lines
+        .iter()
+        .map(|line| {
+            parse(line, table)
+                .unwrap_or_else(|e| panic!("failed to parse {:?}: {}", line, e))
+                .eval()
+                .expect("arithmetic overflow")
+        })
+        .sum::<i64>()
+        .to_string()
+}
+
 impl Solution for Q18 {
-    fn part1(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part1(&self, lines: &ProblemInput) -> String {
+        let table = OperatorTable::new()
+            .operator(Operator::Add, 1, Associativity::Left)
+            .operator(Operator::Multiply, 1, Associativity::Left);
+
+        sum_of_results(lines, &table)
     }
 
-    fn part2(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part2(&self, lines: &ProblemInput) -> String {
+        let table = OperatorTable::new()
+            .operator(Operator::Add, 2, Associativity::Left)
+            .operator(Operator::Multiply, 1, Associativity::Left);
+
+        sum_of_results(lines, &table)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::load_problem_input;
+
+    fn example() -> ProblemInput {
+        ProblemInput::from(vec![
+            "1 + 2 * 3 + 4 * 5 + 6",
+            "2 * 3 + (4 * 5)",
+            "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2",
+        ])
+    }
 
     #[test]
     fn test_part1_solution() {
         let q18 = Q18;
-        assert_eq!(q18.part1(&load_problem_input(1)), String::new());
+        assert_eq!(q18.part1(&example()), (71 + 26 + 13632).to_string());
     }
 
     #[test]
     fn test_part2_solution() {
         let q18 = Q18;
-        assert_eq!(q18.part2(&load_problem_input(1)), String::new());
+        assert_eq!(q18.part2(&example()), (231 + 46 + 23340).to_string());
     }
 }
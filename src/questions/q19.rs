@@ -1,31 +1,132 @@
+use crate::grammar::{Grammar, Rule};
 use crate::{ProblemInput, Solution};
 
 pub struct Q19;
 
+/// The rules, and the messages which follow them after a blank line.
+fn parse_input(lines: &ProblemInput) -> (Grammar, &[String]) {
+    let split = lines
+        .lines
+        .iter()
+        .position(String::is_empty)
+        .expect("missing blank line before messages");
+    let grammar = Grammar::parse(&lines.lines[..split]).unwrap();
+
+    (grammar, &lines.lines[split + 1..])
+}
+
 impl Solution for Q19 {
-    fn part1(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part1(&self, lines: &ProblemInput) -> String {
+        let (grammar, messages) = parse_input(lines);
+        let regex = grammar.to_regex(0).unwrap();
+
+        messages
+            .iter()
+            .filter(|message| regex.is_match(message))
+            .count()
+            .to_string()
     }
 
-    fn part2(&self, _lines: &ProblemInput) -> String {
-        String::new()
+    fn part2(&self, lines: &ProblemInput) -> String {
+        let (mut grammar, messages) = parse_input(lines);
+        grammar
+            .set_rule(8, Rule::Alternatives(vec![vec![42], vec![42, 8]]))
+            .unwrap();
+        grammar
+            .set_rule(11, Rule::Alternatives(vec![vec![42, 31], vec![42, 11, 31]]))
+            .unwrap();
+
+        messages
+            .iter()
+            .filter(|message| grammar.matches(0, message))
+            .count()
+            .to_string()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::load_problem_input;
+
+    fn small_example() -> ProblemInput {
+        ProblemInput::from(vec![
+            "0: 4 1 5",
+            "1: 2 3 | 3 2",
+            "2: 4 4 | 5 5",
+            "3: 4 5 | 5 4",
+            "4: \"a\"",
+            "5: \"b\"",
+            "",
+            "ababbb",
+            "bababa",
+            "abbbab",
+            "aaabbb",
+            "aaaabbb",
+        ])
+    }
+
+    fn recursive_example() -> ProblemInput {
+        ProblemInput::from(vec![
+            "42: 9 14 | 10 1",
+            "9: 14 27 | 1 26",
+            "10: 23 14 | 28 1",
+            "1: \"a\"",
+            "11: 42 31",
+            "5: 1 14 | 15 1",
+            "19: 14 1 | 14 14",
+            "12: 24 14 | 19 1",
+            "16: 15 1 | 14 14",
+            "31: 14 17 | 1 13",
+            "6: 14 14 | 1 14",
+            "2: 1 24 | 14 4",
+            "0: 8 11",
+            "13: 14 3 | 1 12",
+            "15: 1 | 14",
+            "17: 14 2 | 1 7",
+            "23: 25 1 | 22 14",
+            "28: 16 1",
+            "4: 1 1",
+            "20: 14 14 | 1 15",
+            "3: 5 14 | 16 1",
+            "27: 1 6 | 14 18",
+            "14: \"b\"",
+            "21: 14 1 | 1 14",
+            "25: 1 1 | 1 14",
+            "22: 14 14",
+            "8: 42",
+            "26: 14 22 | 1 20",
+            "18: 15 15",
+            "7: 14 5 | 1 21",
+            "24: 14 1",
+            "",
+            "abbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa",
+            "bbabbbbaabaabba",
+            "babbbbaabbbbbabbbbbbaabaaabaaa",
+            "aaabbbbbbaaaabaababaabababbabaaabbababababaaa",
+            "bbbbbbbaaaabbbbaaabbabaaa",
+            "bbbababbbbaaaaaaaabbababaaababaabab",
+            "ababaaaaaabaaab",
+            "ababaaaaabbbaba",
+            "baabbaaaabbaaaababbaababb",
+            "abbbbabbbbaaaababbbbbbaaaababb",
+            "aaaaabbaabaaaaababaa",
+            "aaaabbaaaabbaaa",
+            "aaaabbaabbaaaaaaabbbabbbaaabbaabaaa",
+            "babaaabbbaaabaababbaabababaaab",
+            "aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba",
+        ])
+    }
 
     #[test]
     fn test_part1_solution() {
         let q19 = Q19;
-        assert_eq!(q19.part1(&load_problem_input(1)), String::new());
+        assert_eq!(q19.part1(&small_example()), 2.to_string());
+        assert_eq!(q19.part1(&recursive_example()), 3.to_string());
     }
 
     #[test]
     fn test_part2_solution() {
         let q19 = Q19;
-        assert_eq!(q19.part2(&load_problem_input(1)), String::new());
+        assert_eq!(q19.part2(&recursive_example()), 12.to_string());
     }
 }
@@ -1,58 +1,174 @@
 use crate::{FromProblemInputLine, ProblemInput, Solution};
-use std::ops::RangeInclusive;
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use std::fmt::Write;
+use std::str::FromStr;
 
 pub struct Q2;
 
-struct Rule {
+/// A line of the password database: some numbers, a letter and a password.
+///
+/// The numbers are written either as a range `1-3` or as a list `1,3,5`.
+pub struct Rule {
+    numbers: Vec<usize>,
     c: char,
-    r: RangeInclusive<i32>,
     s: String,
 }
 
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let mut parts = line.split_ascii_whitespace();
+
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(numbers), Some(letter), Some(password), None) => {
+                let numbers = numbers
+                    .split(&['-', ','][..])
+                    .map(|x| x.parse())
+                    .collect::<Result<_, _>>()?;
+
+                let mut letter = letter.chars();
+                let c = match (letter.next(), letter.next(), letter.next()) {
+                    (Some(c), Some(':'), None) => c,
+                    _ => return Err(anyhow!("invalid letter in {}", line)),
+                };
+
+                Ok(Rule {
+                    numbers,
+                    c,
+                    s: password.to_string(),
+                })
+            }
+            _ => Err(anyhow!("invalid rule {}", line)),
+        }
+    }
+}
+
 impl FromProblemInputLine for Rule {
     fn from_line(line: &str) -> Self {
-        let mut parts: Vec<&str> = line.split_ascii_whitespace().collect();
+        line.parse().expect("failed to parse")
+    }
+}
 
-        // Parse the numeric portion
-        let numbers: Vec<_> = parts[0]
-            .split('-')
-            .map(|x| x.parse::<i32>().unwrap())
-            .collect();
+impl Rule {
+    /// The (1-based) positions named by the rule which hold its letter.
+    fn matching_positions(&self) -> Vec<usize> {
+        self.numbers
+            .iter()
+            .copied()
+            .filter(|&p| p >= 1 && self.s.chars().nth(p - 1) == Some(self.c))
+            .collect()
+    }
+}
 
-        // Parse the middle letter
-        let letter = parts[1].chars().next().unwrap();
+/// How a password is checked against its rule.
+#[derive(Debug, Clone)]
+pub enum Policy {
+    /// The letter occurs between the two numbers times, inclusive.
+    CountInRange,
+    /// Exactly one of the positions holds the letter.
+    ExactlyOnePosition,
+    /// Every one of the positions holds the letter.
+    AllPositions,
+    /// The password matches a regular expression, ignoring the rest of the rule.
+    Regex(Regex),
+}
 
-        Rule {
-            c: letter,
-            r: numbers[0]..=numbers[1],
-            s: parts.pop().unwrap().to_string(),
+impl FromStr for Policy {
+    type Err = anyhow::Error;
+
+    /// Policies are named `count`, `one-position`, `all-positions` or `regex:<pattern>`.
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "count" => Ok(Policy::CountInRange),
+            "one-position" => Ok(Policy::ExactlyOnePosition),
+            "all-positions" => Ok(Policy::AllPositions),
+            _ => match s.strip_prefix("regex:") {
+                Some(pattern) => Ok(Policy::Regex(Regex::new(pattern)?)),
+                None => Err(anyhow!("unknown policy {}", s)),
+            },
         }
     }
 }
 
-impl Solution for Q2 {
-    fn part1(&self, lines: &ProblemInput) -> String {
+impl Policy {
+    /// Checks the rule's password, returning whether it passes and why.
+    pub fn check(&self, rule: &Rule) -> (bool, String) {
+        match self {
+            Policy::CountInRange => {
+                let count = rule.s.chars().filter(|&z| z == rule.c).count();
+
+                match rule.numbers.as_slice() {
+                    &[low, high] => (
+                        (low..=high).contains(&count),
+                        format!("{} occurs {} times, need {}-{}", rule.c, count, low, high),
+                    ),
+                    _ => (false, String::from("expected a range of counts")),
+                }
+            }
+            Policy::ExactlyOnePosition => {
+                let matching = rule.matching_positions();
+                (
+                    matching.len() == 1,
+                    format!(
+                        "{} at positions {:?} of {:?}, need exactly one",
+                        rule.c, matching, rule.numbers
+                    ),
+                )
+            }
+            Policy::AllPositions => {
+                let matching = rule.matching_positions();
+                (
+                    matching.len() == rule.numbers.len(),
+                    format!(
+                        "{} at positions {:?} of {:?}, need all",
+                        rule.c, matching, rule.numbers
+                    ),
+                )
+            }
+            Policy::Regex(re) => {
+                let passed = re.is_match(&rule.s);
+                let verb = if passed { "matches" } else { "does not match" };
+                (passed, format!("{} {} /{}/", rule.s, verb, re))
+            }
+        }
+    }
+
+    pub fn count_valid(&self, lines: &ProblemInput) -> usize {
         lines
             .parse::<Vec<Rule>>()
-            .into_iter()
-            .filter(|rule| {
-                rule.r
-                    .contains(&(rule.s.chars().filter(|&z| z == rule.c).count() as i32))
-            })
+            .iter()
+            .filter(|rule| self.check(rule).0)
             .count()
-            .to_string()
+    }
+}
+
+/// Lists whether each line of the input passes the policy and why, followed by
+/// the number of passing lines.
+pub fn explain(lines: &ProblemInput, policy: &Policy) -> String {
+    let mut out = String::new();
+    let mut valid = 0;
+
+    for line in lines.lines.iter() {
+        let (passed, explanation) = policy.check(&Rule::from_line(line));
+        let verdict = if passed { "pass" } else { "fail" };
+        valid += passed as usize;
+
+        writeln!(out, "{}: {} ({})", line, verdict, explanation).unwrap();
+    }
+
+    writeln!(out, "\n{} of {} passwords valid", valid, lines.lines.len()).unwrap();
+    out
+}
+
+impl Solution for Q2 {
+    fn part1(&self, lines: &ProblemInput) -> String {
+        Policy::CountInRange.count_valid(lines).to_string()
     }
 
     fn part2(&self, lines: &ProblemInput) -> String {
-        lines
-            .parse::<Vec<Rule>>()
-            .into_iter()
-            .filter(|rule| {
-                (rule.s.chars().nth((rule.r.start() - 1) as usize).unwrap() == rule.c)
-                    ^ (rule.s.chars().nth((rule.r.end() - 1) as usize).unwrap() == rule.c)
-            })
-            .count()
-            .to_string()
+        Policy::ExactlyOnePosition.count_valid(lines).to_string()
     }
 }
 
@@ -72,4 +188,18 @@ mod tests {
         let q2 = Q2;
         assert_eq!(q2.part2(&load_problem_input(2)), 690.to_string());
     }
+
+    #[test]
+    fn test_policies() {
+        let rules: Vec<Rule> = ProblemInput::from(vec!["1-3 a: abcde", "1,3,5 c: cbcac"]).parse();
+        let check = |policy: &str, rule: &Rule| policy.parse::<Policy>().unwrap().check(rule).0;
+
+        assert!(check("count", &rules[0]));
+        assert!(check("one-position", &rules[0]));
+        assert!(!check("all-positions", &rules[0]));
+        assert!(!check("count", &rules[1]));
+        assert!(check("all-positions", &rules[1]));
+        assert!(check("regex:^c.c", &rules[1]));
+        assert!("nonsense".parse::<Policy>().is_err());
+    }
 }
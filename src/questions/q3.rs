@@ -1,4 +1,7 @@
 use crate::{FromProblemInput, ProblemInput, Solution};
+use num::integer::gcd;
+use num::rational::Ratio;
+use std::ops::RangeInclusive;
 
 pub struct Q3;
 
@@ -12,29 +15,133 @@ impl FromProblemInput for Vec<Vec<bool>> {
     }
 }
 
-fn slope_counter(grid: &[Vec<bool>], right: usize, down: usize) -> usize {
-    grid.iter()
-        .step_by(down)
+/// A direction of travel: `right` columns for every `down` rows.
+///
+/// Slopes are kept in lowest terms, so a fractional slope such as two right for every
+/// four down stops at every lattice point on the line, here every other row.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Slope {
+    pub right: usize,
+    pub down: usize,
+}
+
+impl Slope {
+    pub fn new(right: usize, down: usize) -> Self {
+        assert!(down > 0, "slopes must go down");
+        let g = gcd(right, down);
+
+        Self {
+            right: right / g,
+            down: down / g,
+        }
+    }
+
+    /// Every distinct slope with a step in the given ranges.
+    pub fn all_in(rights: RangeInclusive<usize>, downs: RangeInclusive<usize>) -> Vec<Self> {
+        let mut slopes: Vec<_> = rights
+            .flat_map(|right| downs.clone().map(move |down| (right, down)))
+            .filter(|&(_, down)| down > 0)
+            .map(|(right, down)| Slope::new(right, down))
+            .collect();
+        slopes.sort_by_key(|s| (s.down, s.right));
+        slopes.dedup();
+
+        slopes
+    }
+}
+
+/// Treats the ratio as the number of columns moved per row.
+impl From<Ratio<usize>> for Slope {
+    fn from(r: Ratio<usize>) -> Self {
+        Slope::new(*r.numer(), *r.denom())
+    }
+}
+
+/// The trees hit travelling down a map along a slope.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlopeReport {
+    pub slope: Slope,
+    /// Every position (row, column) stopped at after the start, with the column
+    /// wrapped to the width of the map.
+    pub path: Vec<(usize, usize)>,
+    /// The positions in `path` holding a tree.
+    pub hits: Vec<(usize, usize)>,
+}
+
+pub fn evaluate(grid: &[Vec<bool>], slope: Slope) -> SlopeReport {
+    let path: Vec<_> = grid
+        .iter()
+        .enumerate()
+        .step_by(slope.down)
         .enumerate()
         .skip(1)
-        .filter(|(c, row)| row[(right * c) % row.len()])
-        .count()
+        .map(|(c, (row, line))| (row, (slope.right * c) % line.len()))
+        .collect();
+
+    let hits = path
+        .iter()
+        .copied()
+        .filter(|&(row, col)| grid[row][col])
+        .collect();
+
+    SlopeReport { slope, path, hits }
+}
+
+pub fn evaluate_all(grid: &[Vec<bool>], slopes: &[Slope]) -> Vec<SlopeReport> {
+    slopes.iter().map(|&slope| evaluate(grid, slope)).collect()
+}
+
+/// The slope hitting the fewest trees.
+pub fn best_slope(grid: &[Vec<bool>], slopes: &[Slope]) -> Option<SlopeReport> {
+    evaluate_all(grid, slopes)
+        .into_iter()
+        .min_by_key(|r| r.hits.len())
+}
+
+/// The slope hitting the most trees.
+pub fn worst_slope(grid: &[Vec<bool>], slopes: &[Slope]) -> Option<SlopeReport> {
+    evaluate_all(grid, slopes)
+        .into_iter()
+        .max_by_key(|r| r.hits.len())
+}
+
+/// Draws the map with the path marked: `O` for open squares and `X` for trees.
+pub fn render(grid: &[Vec<bool>], report: &SlopeReport) -> String {
+    let mut rows: Vec<Vec<char>> = grid
+        .iter()
+        .map(|line| line.iter().map(|&t| if t { '#' } else { '.' }).collect())
+        .collect();
+
+    for &(row, col) in report.path.iter() {
+        rows[row][col] = if grid[row][col] { 'X' } else { 'O' };
+    }
+
+    rows.into_iter()
+        .map(|row| row.into_iter().collect::<String>() + "\n")
+        .collect()
 }
 
 impl Solution for Q3 {
     fn part1(&self, lines: &ProblemInput) -> String {
-        slope_counter(&lines.parse::<Vec<Vec<bool>>>(), 3, 1).to_string()
+        let grid = lines.parse::<Vec<Vec<bool>>>();
+        evaluate(&grid, Slope::new(3, 1)).hits.len().to_string()
     }
 
     fn part2(&self, lines: &ProblemInput) -> String {
-        let grid: Vec<Vec<bool>> = lines.parse::<Vec<Vec<bool>>>();
+        let grid = lines.parse::<Vec<Vec<bool>>>();
+        let slopes = [
+            Slope::new(1, 1),
+            Slope::new(3, 1),
+            Slope::new(5, 1),
+            Slope::new(7, 1),
+            Slope::new(1, 2),
+        ];
 
-        (slope_counter(&grid, 1, 1)
-            * slope_counter(&grid, 3, 1)
-            * slope_counter(&grid, 5, 1)
-            * slope_counter(&grid, 7, 1)
-            * slope_counter(&grid, 1, 2))
-        .to_string()
+        evaluate_all(&grid, &slopes)
+            .iter()
+            .map(|r| r.hits.len())
+            .product::<usize>()
+            .to_string()
     }
 }
 
@@ -54,4 +161,31 @@ mod tests {
         let q3 = Q3;
         assert_eq!(q3.part2(&load_problem_input(3)), 727923200.to_string());
     }
+
+    #[test]
+    fn test_fractional_slopes() {
+        let grid = load_problem_input(3).parse::<Vec<Vec<bool>>>();
+
+        assert_eq!(Slope::new(6, 2), Slope::new(3, 1));
+        assert_eq!(Slope::from(Ratio::new(1, 2)), Slope::new(1, 2));
+        assert_eq!(evaluate(&grid, Slope::new(6, 2)).hits.len(), 148);
+
+        let slopes = Slope::all_in(0..=7, 1..=2);
+        let best = best_slope(&grid, &slopes).unwrap();
+        let worst = worst_slope(&grid, &slopes).unwrap();
+        assert!(best.hits.len() <= worst.hits.len());
+        assert!(slopes.iter().all(|&s| {
+            let hits = evaluate(&grid, s).hits.len();
+            best.hits.len() <= hits && hits <= worst.hits.len()
+        }));
+    }
+
+    #[test]
+    fn test_render() {
+        let grid = ProblemInput::from(vec!["..#", "#.#", "..#"]).parse::<Vec<Vec<bool>>>();
+        let report = evaluate(&grid, Slope::new(1, 1));
+
+        assert_eq!(report.hits, vec![(2, 2)]);
+        assert_eq!(render(&grid, &report), "..#\n#O#\n..X\n");
+    }
 }
@@ -1,6 +1,12 @@
-use crate::{FromProblemInput, ProblemInput, Skip, Solution};
-use serde::de::value::{Error, MapDeserializer};
+use crate::records::Records;
+use crate::validation::{
+    digits, height, hex_colour, one_of, year, Failure, Fields, Reason, Validator,
+};
+use crate::{FromProblemInput, ProblemInput, Solution};
 use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::ops::RangeInclusive;
 
 pub struct Q4;
 
@@ -18,102 +24,118 @@ struct Passport {
     cid: String,
 }
 
-impl Passport {
-    fn is_valid(&self) -> bool {
-        // byr: four digits, 1920 <= 2002
-        let byr = match self.byr.parse::<i32>() {
-            Ok(x) => x,
-            Err(_) => return false,
-        };
-
-        if self.byr.len() != 4 || byr < 1920 || byr > 2002 {
-            return false;
-        }
-
-        // iyr: four digits, at least 2010 and at most 2020.
-        let iyr = match self.iyr.parse::<i32>() {
-            Ok(iyr) => iyr,
-            Err(_) => return false,
+impl Fields for Passport {
+    fn field(&self, name: &str) -> Option<&str> {
+        let value = match name {
+            "byr" => &self.byr,
+            "iyr" => &self.iyr,
+            "eyr" => &self.eyr,
+            "hgt" => &self.hgt,
+            "hcl" => &self.hcl,
+            "ecl" => &self.ecl,
+            "pid" => &self.pid,
+            "cid" => &self.cid,
+            _ => return None,
         };
 
-        if self.iyr.len() != 4 || iyr < 2010 || iyr > 2020 {
-            return false;
-        }
-
-        // eyr: four digits; at least 2020 and at most 2030.
-        let eyr = match self.eyr.parse::<i32>() {
-            Ok(x) => x,
-            Err(_) => return false,
-        };
-
-        if self.eyr.len() != 4 || eyr < 2020 || eyr > 2030 {
-            return false;
-        }
-
-        // hgt: a number followed by either cm or in:
-        let bounds = if self.hgt.ends_with("cm") {
-            (150, 193)
-        } else if self.hgt.ends_with("in") {
-            (59, 76)
-        } else {
-            return false;
-        };
-
-        // bounds check
-        match (&self.hgt[..self.hgt.len() - 2]).parse::<i32>() {
-            Ok(x) if x >= bounds.0 && x <= bounds.1 => {}
-            _ => return false,
-        };
-
-        // hcl: a # followed by exactly six characters 0-9 or a-f.
-        if !self.hcl.starts_with('#') || self.hcl.len() != 7 {
-            return false;
-        }
-        // Check that self.hcl[1..] are all 0-9 and a-f
-        for c in self.hcl.chars().skip(1) {
-            if !('0'..='9').contains(&c) && !('a'..='f').contains(&c) {
-                return false;
-            }
-        }
-
-        // ecl: exactly one of: amb blu brn gry grn hzl oth.
-        if !["amb", "blu", "brn", "gry", "grn", "hzl", "oth"].contains(&self.ecl.as_str()) {
-            return false;
-        }
+        Some(value.as_str())
+    }
+}
 
-        // pid: a nine-digit number, including leading zeroes.
-        if self.pid.len() != 9 || self.pid.parse::<i32>().is_err() {
-            return false;
-        }
+fn passport_validator() -> Validator {
+    Validator::new()
+        .field("byr", year(1920..=2002))
+        .field("iyr", year(2010..=2020))
+        .field("eyr", year(2020..=2030))
+        .field("hgt", height(150..=193, 59..=76))
+        .field("hcl", hex_colour())
+        .field(
+            "ecl",
+            one_of(&["amb", "blu", "brn", "gry", "grn", "hzl", "oth"]),
+        )
+        .field("pid", digits(9))
+}
 
-        true
+impl Passport {
+    fn validate(&self) -> Vec<Failure> {
+        passport_validator().validate(self)
     }
-}
 
-impl FromProblemInput for Option<Passport> {
-    fn from(lines: &ProblemInput) -> Self {
-        Passport::deserialize(MapDeserializer::<_, Error>::new(
-            lines
-                .lines
-                .join("\n")
-                .split_ascii_whitespace()
-                .map(|s| (&s[..3], &s[4..])),
-        ))
-        .ok()
+    fn is_valid(&self) -> bool {
+        self.validate().is_empty()
     }
 }
 
 impl FromProblemInput for Vec<Passport> {
     fn from(lines: &ProblemInput) -> Self {
         lines
-            .parse::<Skip<Option<Passport>>>()
+            .parse::<Records>()
             .unwrap()
-            .into_iter()
-            .flatten()
+            .iter()
+            .filter_map(|record| record.deserialize().ok())
             .collect()
     }
 }
 
+/// The outcome of validating a single record.
+#[derive(Debug, Clone)]
+pub struct RecordDiagnostics {
+    /// As in `Record::lines`.
+    pub lines: RangeInclusive<usize>,
+    pub failures: Vec<Failure>,
+}
+
+/// Validates every record in the input, including those missing required fields.
+pub fn diagnose(lines: &ProblemInput) -> Vec<RecordDiagnostics> {
+    let validator = passport_validator();
+
+    lines
+        .parse::<Records>()
+        .unwrap()
+        .into_iter()
+        .map(|record| RecordDiagnostics {
+            failures: validator.validate(&record),
+            lines: record.lines,
+        })
+        .collect()
+}
+
+/// A human readable report listing why each invalid record failed, followed by
+/// the number of records failing for each reason.
+pub fn diagnostics_report(lines: &ProblemInput) -> String {
+    let diagnostics = diagnose(lines);
+    let mut summary: BTreeMap<(String, &str), usize> = BTreeMap::new();
+    let mut out = String::new();
+
+    for record in diagnostics.iter().filter(|r| !r.failures.is_empty()) {
+        let failures: Vec<_> = record.failures.iter().map(Failure::to_string).collect();
+        writeln!(
+            out,
+            "lines {}-{}: {}",
+            record.lines.start(),
+            record.lines.end(),
+            failures.join("; ")
+        )
+        .unwrap();
+
+        for failure in record.failures.iter() {
+            let kind = match failure.reason {
+                Reason::Missing => "missing",
+                Reason::Invalid(_) => "invalid",
+            };
+            *summary.entry((failure.field.clone(), kind)).or_default() += 1;
+        }
+    }
+
+    let valid = diagnostics.iter().filter(|r| r.failures.is_empty()).count();
+    writeln!(out, "\n{} of {} records valid", valid, diagnostics.len()).unwrap();
+    for ((field, kind), count) in summary {
+        writeln!(out, "{} {}: {}", field, kind, count).unwrap();
+    }
+
+    out
+}
+
 impl Solution for Q4 {
     fn part1(&self, lines: &ProblemInput) -> String {
         lines.parse::<Vec<Passport>>().len().to_string()
@@ -145,4 +167,18 @@ mod tests {
         let q4 = Q4;
         assert_eq!(q4.part2(&load_problem_input(4)), 224.to_string());
     }
+
+    #[test]
+    fn test_diagnostics_agree_with_solution() {
+        let diagnostics = diagnose(&load_problem_input(4));
+
+        let complete = diagnostics
+            .iter()
+            .filter(|r| r.failures.iter().all(|f| f.reason != Reason::Missing))
+            .count();
+        let valid = diagnostics.iter().filter(|r| r.failures.is_empty()).count();
+
+        assert_eq!(complete, 264);
+        assert_eq!(valid, 224);
+    }
 }
@@ -1,57 +1,182 @@
-use crate::{FromProblemInputLine, ProblemInput, Solution};
+use crate::{ProblemInput, Solution};
+use anyhow::{anyhow, bail, Result};
+use std::collections::BTreeSet;
 
 pub struct Q5;
 
-#[derive(Copy, Clone, Debug)]
-struct BoardingPass {
-    row: i32,
-    col: i32,
+/// A run of characters encoding a number in binary, most significant bit first.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub width: usize,
+    pub zero: char,
+    pub one: char,
+    /// The value of this field is multiplied by `multiplier` when forming the id.
+    pub multiplier: u64,
 }
 
-impl BoardingPass {
-    fn seat_id(self) -> i32 {
-        self.row * 8 + self.col
-    }
+/// Decodes strings made of consecutive binary fields into an id, the sum of each
+/// field's value times its multiplier, and encodes ids back into strings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Codec {
+    fields: Vec<Field>,
 }
 
-fn binary_parse<IT: Iterator<Item = char>>(it: IT, target: char) -> i32 {
-    it.fold(0, |acc, x| 2 * acc + ((x == target) as i32))
-}
+impl Codec {
+    /// A codec for strings made of `fields` in order.  Every field must be at most 64
+    /// characters wide, so its value fits in a `u64`, and have a non-zero multiplier.
+    pub fn new(fields: Vec<Field>) -> Result<Self> {
+        for (i, field) in fields.iter().enumerate() {
+            if field.width > 64 {
+                bail!("field {} is {} wide, more than 64", i, field.width);
+            }
+            if field.multiplier == 0 {
+                bail!("field {} has a multiplier of 0", i);
+            }
+            if field.zero == field.one {
+                bail!("field {} uses {} for both 0 and 1", i, field.zero);
+            }
+        }
+
+        Ok(Self { fields })
+    }
+
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Seven `F`/`B` characters giving the row and three `L`/`R` characters
+    /// giving the column, with a seat id of `row * 8 + col`.
+    pub fn boarding_pass() -> Self {
+        Self::new(vec![
+            Field {
+                width: 7,
+                zero: 'F',
+                one: 'B',
+                multiplier: 8,
+            },
+            Field {
+                width: 3,
+                zero: 'L',
+                one: 'R',
+                multiplier: 1,
+            },
+        ])
+        .unwrap()
+    }
+
+    /// The value of each field in `s`.
+    pub fn decode_fields(&self, s: &str) -> Result<Vec<u64>> {
+        let mut chars = s.chars();
+        let mut values = Vec::with_capacity(self.fields.len());
+
+        for field in self.fields.iter() {
+            let mut value = 0;
+            for _ in 0..field.width {
+                let bit = match chars.next() {
+                    Some(c) if c == field.zero => 0,
+                    Some(c) if c == field.one => 1,
+                    Some(c) => bail!("unexpected character {} in {}", c, s),
+                    None => bail!("{} is too short", s),
+                };
+                // Fields are at most 64 wide, so this cannot overflow.
+                value = value << 1 | bit;
+            }
+            values.push(value);
+        }
+
+        if chars.next().is_some() {
+            bail!("{} is too long", s);
+        }
+
+        Ok(values)
+    }
+
+    /// The id encoded by `s`, failing if it does not fit in a `u64`.
+    pub fn decode(&self, s: &str) -> Result<u64> {
+        let mut id: u64 = 0;
+
+        for (value, field) in self.decode_fields(s)?.into_iter().zip(self.fields.iter()) {
+            id = value
+                .checked_mul(field.multiplier)
+                .and_then(|v| id.checked_add(v))
+                .ok_or_else(|| anyhow!("the id encoded by {} overflows", s))?;
+        }
+
+        Ok(id)
+    }
+
+    /// Finds the string decoding to `id`.  Fields are filled greedily, largest
+    /// multiplier first, so this fails if `id` has no representation that way.
+    pub fn encode(&self, id: u64) -> Result<String> {
+        let mut order: Vec<usize> = (0..self.fields.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(self.fields[i].multiplier));
+
+        let mut values = vec![0; self.fields.len()];
+        let mut remaining = id;
+        for i in order {
+            let field = self.fields[i];
+            values[i] = remaining / field.multiplier;
+            remaining %= field.multiplier;
+
+            if values[i].checked_shr(field.width as u32).unwrap_or(0) != 0 {
+                bail!("{} does not fit in the {} field", id, field.width);
+            }
+        }
 
-impl FromProblemInputLine for BoardingPass {
-    fn from_line(line: &str) -> Self {
-        Self {
-            row: binary_parse(line.chars().take(7), 'B'),
-            col: binary_parse(line.chars().skip(7), 'R'),
+        if remaining != 0 {
+            bail!("{} cannot be encoded", id);
         }
+
+        Ok(self
+            .fields
+            .iter()
+            .zip(values)
+            .flat_map(|(field, value)| {
+                (0..field.width).rev().map(move |bit| {
+                    if (value >> bit) & 1 == 1 {
+                        field.one
+                    } else {
+                        field.zero
+                    }
+                })
+            })
+            .collect())
+    }
+}
+
+/// Every id between the smallest and largest of `ids` which is not in `ids`.
+pub fn missing_ids(ids: &[u64]) -> Vec<u64> {
+    let present: BTreeSet<u64> = ids.iter().copied().collect();
+
+    match (present.iter().next(), present.iter().next_back()) {
+        (Some(&low), Some(&high)) => (low..=high).filter(|id| !present.contains(id)).collect(),
+        _ => Vec::new(),
     }
 }
 
+fn seat_ids(lines: &ProblemInput) -> Vec<u64> {
+    let codec = Codec::boarding_pass();
+
+    lines
+        .lines
+        .iter()
+        .map(|line| codec.decode(line).expect("failed to decode"))
+        .collect()
+}
+
 impl Solution for Q5 {
     fn part1(&self, lines: &ProblemInput) -> String {
-        lines
-            .parse::<Vec<BoardingPass>>()
-            .into_iter()
-            .map(BoardingPass::seat_id)
-            .max()
-            .unwrap()
-            .to_string()
+        seat_ids(lines).into_iter().max().unwrap().to_string()
     }
 
     fn part2(&self, lines: &ProblemInput) -> String {
-        let mut seat_ids: Vec<_> = lines
-            .parse::<Vec<BoardingPass>>()
-            .into_iter()
-            .map(BoardingPass::seat_id)
-            .collect();
-        seat_ids.sort_unstable();
-
-        seat_ids
+        let seat_ids = seat_ids(lines);
+        let missing = missing_ids(&seat_ids);
+
+        // Our seat is the only missing one whose neighbours are both taken.
+        missing
             .iter()
-            .zip(seat_ids.iter().skip(1))
-            .filter(|(x, y)| *x + 2 == **y)
-            .map(|(x, _)| x + 1)
-            .next()
+            .find(|&&id| seat_ids.contains(&(id - 1)) && seat_ids.contains(&(id + 1)))
             .unwrap()
             .to_string()
     }
@@ -73,4 +198,42 @@ mod tests {
         let q5 = Q5;
         assert_eq!(q5.part2(&load_problem_input(5)), 504.to_string());
     }
+
+    #[test]
+    fn test_codec() {
+        let codec = Codec::boarding_pass();
+
+        assert_eq!(codec.decode_fields("FBFBBFFRLR").unwrap(), vec![44, 5]);
+        assert_eq!(codec.decode("FBFBBFFRLR").unwrap(), 357);
+        assert_eq!(codec.encode(357).unwrap(), "FBFBBFFRLR");
+        assert!(codec.encode(1024).is_err());
+        assert!(codec.decode("FBFBBFFRLX").is_err());
+
+        let field = |width, multiplier| Field {
+            width,
+            zero: '0',
+            one: '1',
+            multiplier,
+        };
+        assert!(Codec::new(vec![field(3, 0)]).is_err());
+        assert!(Codec::new(vec![field(65, 1)]).is_err());
+
+        let wide = Codec::new(vec![field(64, 1), field(1, 2)]).unwrap();
+        // 2^64 - 1 from the first field plus 2 from the second overflows.
+        assert!(wide.decode(&"1".repeat(65)).is_err());
+        assert_eq!(
+            wide.decode(&format!("{}0", "1".repeat(64))).unwrap(),
+            u64::MAX
+        );
+
+        for line in load_problem_input(5).lines {
+            assert_eq!(codec.encode(codec.decode(&line).unwrap()).unwrap(), line);
+        }
+    }
+
+    #[test]
+    fn test_missing_ids() {
+        assert_eq!(missing_ids(&[3, 7, 4, 9]), vec![5, 6, 8]);
+        assert!(missing_ids(&[]).is_empty());
+    }
 }
@@ -1,17 +1,11 @@
-use crate::{FromProblemInputLine, ProblemInput, Skip, Solution};
-use std::collections::HashSet;
+use crate::charset::CharSet;
+use crate::{ProblemInput, Skip, Solution};
 
 pub struct Q6;
 
-impl FromProblemInputLine for HashSet<char> {
-    fn from_line(line: &str) -> Self {
-        line.chars().collect()
-    }
-}
-
-fn apply<P: FnMut(Vec<HashSet<char>>) -> usize>(lines: &ProblemInput, f: P) -> String {
+fn apply<P: FnMut(Vec<CharSet>) -> usize>(lines: &ProblemInput, f: P) -> String {
     lines
-        .parse::<Skip<Vec<HashSet<char>>>>()
+        .parse::<Skip<Vec<CharSet>>>()
         .unwrap()
         .into_iter()
         .map(f)
@@ -21,15 +15,11 @@ fn apply<P: FnMut(Vec<HashSet<char>>) -> usize>(lines: &ProblemInput, f: P) -> S
 
 impl Solution for Q6 {
     fn part1(&self, lines: &ProblemInput) -> String {
-        apply(lines, |hc| {
-            hc.into_iter().flatten().collect::<HashSet<_>>().len()
-        })
+        apply(lines, |group| CharSet::union_all(&group).len())
     }
 
     fn part2(&self, lines: &ProblemInput) -> String {
-        apply(lines, |hc| {
-            hc.into_iter().fold_first(|x, y| &x & &y).unwrap().len()
-        })
+        apply(lines, |group| CharSet::intersection_all(&group).len())
     }
 }
 
@@ -1,9 +1,20 @@
 use crate::{FromProblemInputLine, ProblemInput, Solution};
+use anyhow::{anyhow, bail, Context, Result};
+use petgraph::graph::NodeIndex;
+use petgraph::visit::{Dfs, Reversed};
+use petgraph::Graph;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter, Write};
+use std::str::FromStr;
 
 pub struct Q8;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-enum Instruction {
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(tag = "op", content = "arg", rename_all = "lowercase")]
+pub enum Instruction {
     Nop(i32),
     Acc(i32),
     Jmp(i32),
@@ -11,21 +22,94 @@ enum Instruction {
 
 impl FromProblemInputLine for Instruction {
     fn from_line(line: &str) -> Self {
-        // Parse the numeric bit
-        let arg = (&line[4..]).parse::<i32>().expect("failed to parse");
+        line.parse().expect("failed to parse")
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.split_ascii_whitespace();
+
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(op), Some(arg), None) => {
+                let arg = arg
+                    .parse()
+                    .with_context(|| format!("invalid argument {}", arg))?;
+                Instruction::new(op, arg)
+            }
+            _ => Err(anyhow!("invalid instruction {}", s)),
+        }
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Nop(x) => write!(f, "nop {:+}", x),
+            Instruction::Acc(x) => write!(f, "acc {:+}", x),
+            Instruction::Jmp(x) => write!(f, "jmp {:+}", x),
+        }
+    }
+}
+
+impl Instruction {
+    pub fn new(op: &str, arg: i32) -> Result<Self> {
+        match op {
+            "nop" => Ok(Instruction::Nop(arg)),
+            "acc" => Ok(Instruction::Acc(arg)),
+            "jmp" => Ok(Instruction::Jmp(arg)),
+            op => Err(anyhow!("invalid instruction {}", op)),
+        }
+    }
+
+    /// Returns the instruction obtained by swapping `nop` and `jmp`, or `None`
+    /// for instructions which cannot be corrupted.
+    pub fn flipped(self) -> Option<Self> {
+        match self {
+            Instruction::Nop(x) => Some(Instruction::Jmp(x)),
+            Instruction::Acc(_) => None,
+            Instruction::Jmp(x) => Some(Instruction::Nop(x)),
+        }
+    }
+
+    /// Where control goes after executing this instruction at `pc` in a program of
+    /// length `len`.
+    pub fn successor(self, pc: usize, len: usize) -> Successor {
+        let next = match self {
+            Instruction::Jmp(j) => pc as i64 + j as i64,
+            _ => pc as i64 + 1,
+        };
 
-        match &line[..3] {
-            "nop" => Instruction::Nop(arg),
-            "acc" => Instruction::Acc(arg),
-            "jmp" => Instruction::Jmp(arg),
-            instruction => panic!("invalid instruction {}", instruction),
+        if next < 0 || next > len as i64 {
+            Successor::Invalid
+        } else if next == len as i64 {
+            Successor::Exit
+        } else {
+            Successor::Instruction(next as usize)
         }
     }
 }
 
-struct Interpreter<'a> {
+/// The outcome of executing an instruction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Successor {
+    /// Execution continues at this index.
+    Instruction(usize),
+    /// Execution moves to just past the last instruction, terminating the program.
+    Exit,
+    /// Execution jumps outside the program, which is not a valid way to terminate.
+    Invalid,
+}
+
+#[derive(Debug, Clone)]
+pub struct Interpreter<'a> {
     instructions: &'a [Instruction],
 
+    // A single instruction overriding the one in `instructions`, set when forking.
+    patch: Option<Patch>,
+
     // Forward defensive play
     accumulators: Vec<i32>,
     current_accumulator: usize,
@@ -35,16 +119,17 @@ struct Interpreter<'a> {
 }
 
 impl<'a> Interpreter<'a> {
-    fn new(instructions: &'a [Instruction]) -> Self {
+    pub fn new(instructions: &'a [Instruction]) -> Self {
         Self {
             instructions,
+            patch: None,
             accumulators: vec![0],
             current_accumulator: 0,
             current_instruction: 0,
         }
     }
 
-    fn current_accumulator(&self) -> i32 {
+    pub fn current_accumulator(&self) -> i32 {
         self.accumulators[self.current_accumulator]
     }
 
@@ -52,16 +137,58 @@ impl<'a> Interpreter<'a> {
         &mut self.accumulators[self.current_accumulator]
     }
 
-    fn current_instruction(&self) -> usize {
+    pub fn current_instruction(&self) -> usize {
         self.current_instruction
     }
 
-    fn has_terminated(&self) -> bool {
-        self.current_instruction() >= self.instructions.len()
+    /// Whether the program terminated, by moving to just past its last instruction.
+    pub fn has_terminated(&self) -> bool {
+        self.current_instruction() == self.instructions.len()
+    }
+
+    /// Whether the program jumped outside itself, other than by terminating.
+    pub fn has_crashed(&self) -> bool {
+        self.current_instruction() > self.instructions.len()
+    }
+
+    /// Whether there is an instruction to execute, i.e. neither terminated nor crashed.
+    pub fn is_running(&self) -> bool {
+        self.current_instruction() < self.instructions.len()
     }
 
-    fn step(&mut self) {
-        match self.instructions[self.current_instruction] {
+    /// The instruction at `pc`, taking into account any patch applied to this interpreter.
+    pub fn fetch(&self, pc: usize) -> Instruction {
+        match self.patch {
+            Some(patch) if patch.index == pc => patch.instruction,
+            _ => self.instructions[pc],
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            accumulators: self.accumulators.clone(),
+            current_accumulator: self.current_accumulator,
+            current_instruction: self.current_instruction,
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.accumulators = snapshot.accumulators.clone();
+        self.current_accumulator = snapshot.current_accumulator;
+        self.current_instruction = snapshot.current_instruction;
+    }
+
+    /// Creates a copy of this interpreter, in its current state, which sees `patch`
+    /// applied to its program.  The original interpreter is unaffected.
+    pub fn fork(&self, patch: Patch) -> Self {
+        Self {
+            patch: Some(patch),
+            ..self.clone()
+        }
+    }
+
+    pub fn step(&mut self) {
+        match self.fetch(self.current_instruction) {
             Instruction::Nop(_) => {
                 self.current_instruction += 1;
             }
@@ -70,14 +197,17 @@ impl<'a> Interpreter<'a> {
                 self.current_instruction += 1;
             }
             Instruction::Jmp(j) => {
-                self.current_instruction = ((self.current_instruction as i32) + j) as usize;
+                // Jumping before the start leaves the interpreter crashed, like jumping
+                // more than one past the end.
+                let next = self.current_instruction as i64 + j as i64;
+                self.current_instruction = usize::try_from(next).unwrap_or(usize::MAX);
             }
         }
     }
 
     /// Will repeatedly step the interpreter until `pred` evaluates to false.  If `pred`
     /// evaluates to false initially then the interpreter will not be ran at all.
-    fn step_while<P: FnMut(&Self) -> bool>(&mut self, mut pred: P) {
+    pub fn step_while<P: FnMut(&Self) -> bool>(&mut self, mut pred: P) {
         while pred(self) {
             self.step();
         }
@@ -89,13 +219,13 @@ impl<'a> Interpreter<'a> {
 /// or we visit an instruction twice.
 ///
 /// Returns the interpreter after completion.
-fn run_interpreter(instructions: &[Instruction]) -> Interpreter {
+pub fn run_interpreter(instructions: &[Instruction]) -> Interpreter {
     let mut seen = vec![false; instructions.len()];
     let mut interpreter = Interpreter::new(instructions);
 
     interpreter.step_while(|ip| {
         let inst = ip.current_instruction();
-        if ip.has_terminated() || seen[inst] {
+        if !ip.is_running() || seen[inst] {
             false
         } else {
             seen[inst] = true;
@@ -106,6 +236,459 @@ fn run_interpreter(instructions: &[Instruction]) -> Interpreter {
     interpreter
 }
 
+/// The state of an interpreter at a point in time, excluding its program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    accumulators: Vec<i32>,
+    current_accumulator: usize,
+    current_instruction: usize,
+}
+
+/// Tries every single-instruction mutation proposed by `mutate` along the execution
+/// path of the unpatched program, returning each patch which makes the program terminate
+/// along with the final accumulator.
+///
+/// Rather than rerunning the program from scratch for every candidate, the unpatched
+/// program is run once and each candidate forks from the state just before the mutated
+/// instruction.  If `parallel` is set the forks are explored using rayon.
+pub fn search_mutations<M>(
+    instructions: &[Instruction],
+    mutate: M,
+    parallel: bool,
+) -> Vec<(Patch, i32)>
+where
+    M: Fn(Instruction) -> Vec<Instruction>,
+{
+    // For each instruction, the step at which the unpatched program first reached it.
+    let mut first_visit = vec![None; instructions.len()];
+    let mut interpreter = Interpreter::new(instructions);
+    let mut forks = Vec::new();
+    let mut step = 0;
+
+    while interpreter.is_running() {
+        let pc = interpreter.current_instruction();
+        if first_visit[pc].is_some() {
+            break;
+        }
+        first_visit[pc] = Some(step);
+
+        for instruction in mutate(instructions[pc]) {
+            forks.push((
+                step,
+                interpreter.fork(Patch {
+                    index: pc,
+                    instruction,
+                }),
+            ));
+        }
+
+        interpreter.step();
+        step += 1;
+    }
+
+    let explore = |(step, fork): (usize, Interpreter)| explore_fork(fork, &first_visit, step);
+
+    if parallel {
+        forks.into_par_iter().filter_map(explore).collect()
+    } else {
+        forks.into_iter().filter_map(explore).collect()
+    }
+}
+
+/// Runs a forked interpreter to completion, returning its patch and final accumulator if
+/// it terminates.  `first_visit` is shared with the unpatched run: reaching any instruction
+/// the unpatched run executed before step `step` means we will loop back to the patch.
+fn explore_fork(
+    mut fork: Interpreter,
+    first_visit: &[Option<usize>],
+    step: usize,
+) -> Option<(Patch, i32)> {
+    let mut seen = HashSet::new();
+
+    while fork.is_running() {
+        let pc = fork.current_instruction();
+        let in_prefix = matches!(first_visit[pc], Some(s) if s < step);
+
+        if in_prefix || !seen.insert(pc) {
+            return None;
+        }
+
+        fork.step();
+    }
+
+    if fork.has_terminated() {
+        Some((fork.patch.unwrap(), fork.current_accumulator()))
+    } else {
+        None
+    }
+}
+
+/// Writes a program out in the puzzle input format, one instruction per line.
+pub struct Listing<'a>(pub &'a [Instruction]);
+
+impl Display for Listing<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for instruction in self.0 {
+            writeln!(f, "{}", instruction)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn is_label(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Assembles a program written in the puzzle input format, extended with:
+///
+/// - comments, running from a `#` to the end of the line,
+/// - labels, written as `name:` either on their own line or before an instruction,
+/// - label operands, which are resolved to the relative offset of the label.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>> {
+    let mut labels = HashMap::new();
+    let mut raw = Vec::new();
+
+    // First pass: strip comments and work out where each label points.
+    for (line_no, line) in source.lines().enumerate() {
+        let line_no = line_no + 1;
+        let mut line = line.split('#').next().unwrap().trim();
+
+        while let Some(colon) = line.find(':') {
+            let label = line[..colon].trim();
+            if !is_label(label) {
+                bail!("line {}: invalid label {:?}", line_no, label);
+            }
+            if labels.insert(label, raw.len()).is_some() {
+                bail!("line {}: duplicate label {}", line_no, label);
+            }
+            line = line[colon + 1..].trim();
+        }
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut parts = line.split_ascii_whitespace();
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(op), Some(operand), None) => raw.push((line_no, op, operand)),
+            _ => bail!("line {}: expected an operation and an operand", line_no),
+        }
+    }
+
+    // Second pass: resolve label operands into relative offsets.
+    raw.into_iter()
+        .enumerate()
+        .map(|(pc, (line_no, op, operand))| {
+            let arg = if let Ok(arg) = operand.parse::<i32>() {
+                arg
+            } else if let Some(&target) = labels.get(operand) {
+                if op == "acc" {
+                    bail!("line {}: acc does not take a label", line_no);
+                }
+                target as i32 - pc as i32
+            } else {
+                bail!("line {}: unknown label {}", line_no, operand);
+            };
+
+            Instruction::new(op, arg).with_context(|| format!("line {}", line_no))
+        })
+        .collect()
+}
+
+/// Disassembles a program, introducing a label `L<pc>` for every jump target
+/// (including the end of the program) and writing jumps in terms of these labels.
+/// The output can be fed back through `assemble`.
+pub fn disassemble(instructions: &[Instruction]) -> String {
+    let len = instructions.len();
+    let target = |pc: usize, j: i32| -> Option<usize> {
+        let t = pc as i64 + j as i64;
+        if t >= 0 && t <= len as i64 {
+            Some(t as usize)
+        } else {
+            None
+        }
+    };
+
+    let targets: BTreeSet<usize> = instructions
+        .iter()
+        .enumerate()
+        .filter_map(|(pc, inst)| match *inst {
+            Instruction::Jmp(j) => target(pc, j),
+            _ => None,
+        })
+        .collect();
+
+    let mut out = String::new();
+    for (pc, &inst) in instructions.iter().enumerate() {
+        if targets.contains(&pc) {
+            writeln!(out, "L{}:", pc).unwrap();
+        }
+
+        match inst {
+            Instruction::Jmp(j) if target(pc, j).is_some() => {
+                let label = format!("L{}", target(pc, j).unwrap());
+                writeln!(out, "    jmp {:<8} # {:+}", label, j).unwrap();
+            }
+            inst => writeln!(out, "    {}", inst).unwrap(),
+        }
+    }
+
+    // Jumping to just past the last instruction is how a program terminates.
+    if targets.contains(&len) {
+        writeln!(out, "L{}:", len).unwrap();
+    }
+
+    out
+}
+
+/// A single executed instruction, along with the accumulator on either side of it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub struct TraceStep {
+    pub pc: usize,
+    pub instruction: Instruction,
+    pub accumulator_before: i32,
+    pub accumulator_after: i32,
+}
+
+/// Describes the cycle an interpreter got stuck in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LoopReport {
+    /// The first instruction to be executed twice.
+    pub entry: usize,
+    /// The number of instructions in the cycle.
+    pub length: usize,
+    /// The instructions making up the cycle, in execution order.
+    pub instructions: Vec<(usize, Instruction)>,
+}
+
+impl LoopReport {
+    fn from_steps(steps: &[TraceStep]) -> Self {
+        Self {
+            entry: steps[0].pc,
+            length: steps.len(),
+            instructions: steps.iter().map(|s| (s.pc, s.instruction)).collect(),
+        }
+    }
+}
+
+/// The full record of a run of the interpreter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Trace {
+    pub steps: Vec<TraceStep>,
+    pub terminated: bool,
+    pub accumulator: i32,
+    pub loop_report: Option<LoopReport>,
+}
+
+impl Trace {
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Like `run_interpreter`, but records every step taken and, if the program
+/// does not terminate, a report describing the loop it got stuck in.
+pub fn trace_interpreter(instructions: &[Instruction]) -> Trace {
+    // For each instruction, the index in `steps` at which we first executed it.
+    let mut first_visit = vec![None; instructions.len()];
+    let mut interpreter = Interpreter::new(instructions);
+    let mut steps = Vec::new();
+    let mut loop_report = None;
+
+    while interpreter.is_running() {
+        let pc = interpreter.current_instruction();
+
+        if let Some(start) = first_visit[pc] {
+            loop_report = Some(LoopReport::from_steps(&steps[start..]));
+            break;
+        }
+        first_visit[pc] = Some(steps.len());
+
+        let accumulator_before = interpreter.current_accumulator();
+        interpreter.step();
+
+        steps.push(TraceStep {
+            pc,
+            instruction: instructions[pc],
+            accumulator_before,
+            accumulator_after: interpreter.current_accumulator(),
+        });
+    }
+
+    Trace {
+        steps,
+        terminated: interpreter.has_terminated(),
+        accumulator: interpreter.current_accumulator(),
+        loop_report,
+    }
+}
+
+/// A node in the control flow graph of a program.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CfgNode {
+    Instruction(usize, Instruction),
+    Exit,
+}
+
+impl Display for CfgNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CfgNode::Instruction(pc, inst) => write!(f, "{}: {}", pc, inst),
+            CfgNode::Exit => write!(f, "exit"),
+        }
+    }
+}
+
+/// How control passes from one instruction to the next.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CfgEdge {
+    Next,
+    Jump,
+}
+
+impl Display for CfgEdge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CfgEdge::Next => write!(f, "next"),
+            CfgEdge::Jump => write!(f, "jump"),
+        }
+    }
+}
+
+/// Replacing the instruction at `index` with `instruction`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Patch {
+    pub index: usize,
+    pub instruction: Instruction,
+}
+
+impl Patch {
+    pub fn apply(&self, instructions: &mut [Instruction]) {
+        instructions[self.index] = self.instruction;
+    }
+}
+
+/// The control flow graph of a program: every instruction has an edge to the
+/// instruction executed after it, with instructions that terminate the program
+/// pointing to a dedicated exit node.  Instructions which jump outside the program
+/// have no outgoing edge.
+pub struct ControlFlowGraph {
+    pub graph: Graph<CfgNode, CfgEdge>,
+    nodes: Vec<NodeIndex>,
+    exit: NodeIndex,
+}
+
+impl ControlFlowGraph {
+    pub fn new(instructions: &[Instruction]) -> Self {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = instructions
+            .iter()
+            .enumerate()
+            .map(|(pc, &inst)| graph.add_node(CfgNode::Instruction(pc, inst)))
+            .collect();
+        let exit = graph.add_node(CfgNode::Exit);
+
+        for (pc, inst) in instructions.iter().enumerate() {
+            let target = match inst.successor(pc, instructions.len()) {
+                Successor::Instruction(next) => nodes[next],
+                Successor::Exit => exit,
+                Successor::Invalid => continue,
+            };
+            let edge = match inst {
+                Instruction::Jmp(_) => CfgEdge::Jump,
+                _ => CfgEdge::Next,
+            };
+            graph.add_edge(nodes[pc], target, edge);
+        }
+
+        Self { graph, nodes, exit }
+    }
+
+    pub fn exit(&self) -> NodeIndex {
+        self.exit
+    }
+
+    pub fn node(&self, pc: usize) -> NodeIndex {
+        self.nodes[pc]
+    }
+
+    /// For each instruction, whether starting execution there terminates.
+    pub fn terminating(&self) -> Vec<bool> {
+        let reversed = Reversed(&self.graph);
+        let mut dfs = Dfs::new(reversed, self.exit);
+        while dfs.next(reversed).is_some() {}
+
+        self.nodes
+            .iter()
+            .map(|n| dfs.discovered.contains(n.index()))
+            .collect()
+    }
+
+    /// Finds a single `nop`/`jmp` swap which makes the program terminate.
+    ///
+    /// Every instruction has exactly one successor, so the instructions executed
+    /// before the patch are exactly those on the path from the start, and the patch
+    /// works if the flipped instruction's successor can reach the exit.
+    pub fn find_patch(&self, instructions: &[Instruction]) -> Option<Patch> {
+        let terminating = self.terminating();
+        let mut seen = vec![false; instructions.len()];
+        let mut pc = 0;
+
+        while pc < instructions.len() && !seen[pc] {
+            seen[pc] = true;
+
+            if let Some(flipped) = instructions[pc].flipped() {
+                let fixed = match flipped.successor(pc, instructions.len()) {
+                    Successor::Instruction(next) => terminating[next],
+                    Successor::Exit => true,
+                    Successor::Invalid => false,
+                };
+
+                if fixed {
+                    return Some(Patch {
+                        index: pc,
+                        instruction: flipped,
+                    });
+                }
+            }
+
+            pc = match instructions[pc].successor(pc, instructions.len()) {
+                Successor::Instruction(next) => next,
+                Successor::Exit | Successor::Invalid => return None,
+            };
+        }
+
+        None
+    }
+}
+
+/// Finds a patch making the program terminate by trying every `nop`/`jmp` swap in
+/// turn.  This is quadratic, but makes a useful cross-check for `ControlFlowGraph`.
+pub fn find_patch_brute_force(instructions: &[Instruction]) -> Option<Patch> {
+    let mut instructions = instructions.to_vec();
+
+    for i in 0..instructions.len() {
+        let curr = instructions[i];
+        let flipped = match curr.flipped() {
+            Some(flipped) => flipped,
+            None => continue,
+        };
+
+        instructions[i] = flipped;
+        if run_interpreter(&instructions).has_terminated() {
+            return Some(Patch {
+                index: i,
+                instruction: flipped,
+            });
+        }
+        instructions[i] = curr;
+    }
+
+    None
+}
+
 impl Solution for Q8 {
     fn part1(&self, lines: &ProblemInput) -> String {
         let instructions = lines.parse::<Vec<Instruction>>();
@@ -116,24 +699,14 @@ impl Solution for Q8 {
     fn part2(&self, lines: &ProblemInput) -> String {
         let mut instructions = lines.parse::<Vec<Instruction>>();
 
-        // Just try modifying each nop/jmp instruction.
-        for i in 0..instructions.len() {
-            let curr = instructions[i];
-            instructions[i] = match curr {
-                Instruction::Nop(x) => Instruction::Jmp(x),
-                Instruction::Acc(_) => {
-                    continue;
-                }
-                Instruction::Jmp(x) => Instruction::Nop(x),
-            };
+        let patch = ControlFlowGraph::new(&instructions)
+            .find_patch(&instructions)
+            .expect("no single instruction patch found");
+        patch.apply(&mut instructions);
 
-            let interpreter = run_interpreter(&instructions);
-            if interpreter.has_terminated() {
-                return interpreter.current_accumulator().to_string();
-            }
-            instructions[i] = curr;
-        }
-        unreachable!()
+        run_interpreter(&instructions)
+            .current_accumulator()
+            .to_string()
     }
 }
 
@@ -153,4 +726,205 @@ mod tests {
         let q8 = Q8;
         assert_eq!(q8.part2(&load_problem_input(8)), 1358.to_string());
     }
+
+    #[test]
+    fn test_trace_loop_report() {
+        let instructions = ProblemInput::from(vec![
+            "nop +0", "acc +1", "jmp +4", "acc +3", "jmp -3", "acc -99", "acc +1", "jmp -4",
+            "acc +6",
+        ])
+        .parse::<Vec<Instruction>>();
+
+        let trace = trace_interpreter(&instructions);
+        assert!(!trace.terminated);
+        assert_eq!(trace.accumulator, 5);
+        assert_eq!(trace.steps.len(), 7);
+
+        let report = trace.loop_report.unwrap();
+        assert_eq!(report.entry, 1);
+        assert_eq!(report.length, 6);
+        assert_eq!(
+            report
+                .instructions
+                .iter()
+                .map(|(pc, _)| *pc)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 6, 7, 3, 4]
+        );
+    }
+
+    #[test]
+    fn test_trace_to_json() {
+        let looping =
+            ProblemInput::from(vec!["nop +0", "acc +1", "jmp -2"]).parse::<Vec<Instruction>>();
+        let json: serde_json::Value =
+            serde_json::from_str(&trace_interpreter(&looping).to_json().unwrap()).unwrap();
+
+        assert_eq!(
+            json["steps"][1],
+            serde_json::json!({
+                "pc": 1,
+                "instruction": { "op": "acc", "arg": 1 },
+                "accumulator_before": 0,
+                "accumulator_after": 1,
+            })
+        );
+        assert_eq!(json["terminated"], false);
+        assert_eq!(json["loop_report"]["entry"], 0);
+        assert_eq!(json["loop_report"]["length"], 3);
+        assert_eq!(
+            json["loop_report"]["instructions"][2],
+            serde_json::json!([2, { "op": "jmp", "arg": -2 }])
+        );
+
+        let terminating = ProblemInput::from(vec!["nop +0", "acc +1"]).parse::<Vec<Instruction>>();
+        let json: serde_json::Value =
+            serde_json::from_str(&trace_interpreter(&terminating).to_json().unwrap()).unwrap();
+
+        assert_eq!(json["terminated"], true);
+        assert_eq!(json["accumulator"], 1);
+        assert!(json.as_object().unwrap().contains_key("loop_report"));
+        assert!(json["loop_report"].is_null());
+    }
+
+    #[test]
+    fn test_cfg_patch_matches_brute_force() {
+        let instructions = load_problem_input(8).parse::<Vec<Instruction>>();
+        let patch = ControlFlowGraph::new(&instructions).find_patch(&instructions);
+
+        assert!(patch.is_some());
+        assert_eq!(patch, find_patch_brute_force(&instructions));
+    }
+
+    #[test]
+    fn test_jumps_outside_program() {
+        let instructions = vec![Instruction::Nop(-5), Instruction::Jmp(0)];
+
+        assert_eq!(Instruction::Jmp(-5).successor(0, 2), Successor::Invalid);
+        assert_eq!(Instruction::Jmp(3).successor(0, 2), Successor::Invalid);
+        assert_eq!(Instruction::Jmp(2).successor(0, 2), Successor::Exit);
+        assert_eq!(Instruction::Nop(0).successor(1, 2), Successor::Exit);
+
+        let mut crashed = instructions.clone();
+        Patch {
+            index: 0,
+            instruction: Instruction::Jmp(-5),
+        }
+        .apply(&mut crashed);
+        let interpreter = run_interpreter(&crashed);
+        assert!(interpreter.has_crashed() && !interpreter.has_terminated());
+
+        // Flipping the `nop -5` would jump before the start, so the only fix is to
+        // flip the `jmp +0`.
+        let fix = Patch {
+            index: 1,
+            instruction: Instruction::Nop(0),
+        };
+        let flip = |inst: Instruction| inst.flipped().into_iter().collect();
+        assert_eq!(
+            ControlFlowGraph::new(&instructions).find_patch(&instructions),
+            Some(fix)
+        );
+        assert_eq!(find_patch_brute_force(&instructions), Some(fix));
+        assert_eq!(search_mutations(&instructions, flip, false), vec![(fix, 0)]);
+    }
+
+    #[test]
+    fn test_assembler_round_trip() {
+        let input = load_problem_input(8);
+        let instructions = input.parse::<Vec<Instruction>>();
+
+        assert_eq!(
+            Listing(&instructions)
+                .to_string()
+                .lines()
+                .collect::<Vec<_>>(),
+            input.lines
+        );
+        assert_eq!(assemble(&disassemble(&instructions)).unwrap(), instructions);
+    }
+
+    #[test]
+    fn test_assembler_labels() {
+        let source =
+            "start: nop +0 # comment\n\nloop:\n    acc +1\n    jmp end\n    jmp loop\nend:";
+
+        assert_eq!(
+            assemble(source).unwrap(),
+            vec![
+                Instruction::Nop(0),
+                Instruction::Acc(1),
+                Instruction::Jmp(2),
+                Instruction::Jmp(-2)
+            ]
+        );
+        assert!(assemble("acc start").is_err());
+        assert!(assemble("a: a: nop +0").is_err());
+    }
+
+    #[test]
+    fn test_search_mutations() {
+        let instructions = load_problem_input(8).parse::<Vec<Instruction>>();
+        let flip = |inst: Instruction| inst.flipped().into_iter().collect();
+
+        let sequential = search_mutations(&instructions, flip, false);
+        let parallel = search_mutations(&instructions, flip, true);
+
+        assert_eq!(sequential, parallel);
+        assert_eq!(sequential.len(), 1);
+        assert_eq!(Some(sequential[0].0), find_patch_brute_force(&instructions));
+        assert_eq!(sequential[0].1, 1358);
+    }
+
+    /// Steps `interpreter` at most `limit` times, stopping early like `run_interpreter`
+    /// once it stops running or reaches an instruction marked in `seen`.
+    fn run_for(interpreter: &mut Interpreter, seen: &mut [bool], limit: usize) {
+        for _ in 0..limit {
+            let pc = interpreter.current_instruction();
+            if !interpreter.is_running() || seen[pc] {
+                break;
+            }
+            seen[pc] = true;
+            interpreter.step();
+        }
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let instructions = load_problem_input(8).parse::<Vec<Instruction>>();
+        let expected = run_interpreter(&instructions);
+
+        let mut seen = vec![false; instructions.len()];
+        let mut interpreter = Interpreter::new(&instructions);
+        run_for(&mut interpreter, &mut seen, 10);
+
+        let snapshot = interpreter.snapshot();
+        let seen_at_snapshot = seen.clone();
+        run_for(&mut interpreter, &mut seen, 5);
+        assert_ne!(interpreter.snapshot(), snapshot);
+        interpreter.restore(&snapshot);
+        seen = seen_at_snapshot;
+
+        // A fork which jumps straight to the exit terminates without touching its parent.
+        let pc = interpreter.current_instruction();
+        let mut fork = interpreter.fork(Patch {
+            index: pc,
+            instruction: Instruction::Jmp((instructions.len() - pc) as i32),
+        });
+        fork.step();
+        assert!(fork.has_terminated());
+        assert_eq!(interpreter.snapshot(), snapshot);
+        assert_eq!(interpreter.fetch(pc), instructions[pc]);
+
+        // Carrying on from the restored state ends up where an uninterrupted run does.
+        run_for(&mut interpreter, &mut seen, usize::MAX);
+        assert_eq!(
+            interpreter.current_accumulator(),
+            expected.current_accumulator()
+        );
+        assert_eq!(
+            interpreter.current_instruction(),
+            expected.current_instruction()
+        );
+    }
 }
@@ -1,45 +1,111 @@
 use crate::{ProblemInput, Solution};
-use itertools::Itertools;
+use anyhow::{bail, Result};
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
 
 pub struct Q9;
 
-impl Solution for Q9 {
-    fn part1(&self, lines: &ProblemInput) -> String {
-        let nums = lines.parse::<Vec<i64>>();
+/// Checks that every number after the preamble is the sum of two of the
+/// `preamble` numbers immediately before it.
+///
+/// The sums of every pair in the window are kept in a multiset, so each number
+/// costs `O(preamble)` rather than `O(preamble^2)` to check.
+pub struct XmasValidator {
+    preamble: usize,
+    window: VecDeque<i64>,
+    sums: HashMap<i64, usize>,
+}
+
+impl XmasValidator {
+    /// Fails if the preamble is empty, as then there is no window to slide.
+    pub fn new(preamble: usize) -> Result<Self> {
+        if preamble == 0 {
+            bail!("the preamble must contain at least one number");
+        }
+
+        Ok(Self {
+            preamble,
+            window: VecDeque::with_capacity(preamble),
+            sums: HashMap::new(),
+        })
+    }
 
-        for w in nums.windows(26) {
-            if !w[..25].iter().combinations(2).any(|x| x[0] + x[1] == w[25]) {
-                return w[25].to_string();
+    /// Feeds the next number to the validator, returning whether it is valid.
+    /// Numbers in the preamble are always valid.
+    pub fn push(&mut self, n: i64) -> bool {
+        let valid = self.window.len() < self.preamble || self.sums.contains_key(&n);
+
+        if self.window.len() == self.preamble {
+            let oldest = self.window.pop_front().unwrap();
+            for &w in self.window.iter() {
+                let count = self.sums.get_mut(&(oldest + w)).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    self.sums.remove(&(oldest + w));
+                }
             }
         }
 
-        unreachable!()
+        for &w in self.window.iter() {
+            *self.sums.entry(n + w).or_default() += 1;
+        }
+        self.window.push_back(n);
+
+        valid
     }
+}
 
-    fn part2(&self, lines: &ProblemInput) -> String {
-        let nums = lines.parse::<Vec<i64>>();
+/// Every number which is not the sum of two of the preceding `preamble` numbers,
+/// along with its index.
+pub fn invalid_numbers(nums: &[i64], preamble: usize) -> Result<Vec<(usize, i64)>> {
+    let mut validator = XmasValidator::new(preamble)?;
 
-        // Compute partial sums
-        let mut partial_sums = vec![0];
-        let mut total = 0;
+    Ok(nums
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|&(_, n)| !validator.push(n))
+        .collect())
+}
+
+/// Finds a contiguous range of at least two numbers summing to `target`.
+///
+/// The window is grown on the right while its sum is too small and shrunk on the
+/// left while it is too large, which requires the numbers to be non-negative.
+pub fn contiguous_range(nums: &[i64], target: i64) -> Option<Range<usize>> {
+    let mut start = 0;
+    let mut sum = 0;
+
+    for (end, &n) in nums.iter().enumerate() {
+        sum += n;
 
-        for &n in nums.iter() {
-            total += n;
-            partial_sums.push(total);
+        while sum > target && start < end {
+            sum -= nums[start];
+            start += 1;
         }
 
-        // Find a contiguous set of at least two numbers summing to 3199139634
-        for i in 0..nums.len() {
-            for j in i + 1..nums.len() {
-                if partial_sums[j + 1] - partial_sums[i] == 3199139634 {
-                    let mut range = nums[i..=j].to_vec();
-                    range.sort_unstable();
-                    return (range[0] + range[range.len() - 1]).to_string();
-                }
-            }
+        if sum == target && end > start {
+            return Some(start..end + 1);
         }
+    }
+
+    None
+}
+
+fn first_invalid(nums: &[i64]) -> i64 {
+    invalid_numbers(nums, 25).unwrap()[0].1
+}
 
-        unreachable!()
+impl Solution for Q9 {
+    fn part1(&self, lines: &ProblemInput) -> String {
+        first_invalid(&lines.parse::<Vec<i64>>()).to_string()
+    }
+
+    fn part2(&self, lines: &ProblemInput) -> String {
+        let nums = lines.parse::<Vec<i64>>();
+        let range = &nums[contiguous_range(&nums, first_invalid(&nums)).unwrap()];
+
+        (range.iter().min().unwrap() + range.iter().max().unwrap()).to_string()
     }
 }
 
@@ -59,4 +125,26 @@ mod tests {
         let q9 = Q9;
         assert_eq!(q9.part2(&load_problem_input(9)), 438559930_i64.to_string());
     }
+
+    #[test]
+    fn test_example() {
+        let nums = vec![
+            35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309,
+            576,
+        ];
+
+        assert_eq!(invalid_numbers(&nums, 5).unwrap(), vec![(14, 127)]);
+        assert_eq!(invalid_numbers(&nums, 1).unwrap().len(), nums.len() - 1);
+        assert_eq!(contiguous_range(&nums, 127), Some(2..6));
+        assert_eq!(contiguous_range(&nums, 35), Some(1..3));
+        assert_eq!(contiguous_range(&nums, 1), None);
+    }
+
+    #[test]
+    fn test_empty_preamble() {
+        assert_eq!(
+            invalid_numbers(&[1, 2, 3], 0).unwrap_err().to_string(),
+            "the preamble must contain at least one number"
+        );
+    }
 }
@@ -0,0 +1,250 @@
+use crate::validation::Fields;
+use crate::{FromProblemInput, ProblemInput};
+use anyhow::{anyhow, Result};
+use serde::de::value::{BorrowedStrDeserializer, Error, MapDeserializer};
+use serde::de::{self, Deserialize, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use std::ops::RangeInclusive;
+
+/// How the key/value pairs of a record are written.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RecordFormat {
+    /// Separates pairs on the same line; `None` splits on any whitespace.
+    /// Line breaks always separate pairs.
+    pub pair_separator: Option<char>,
+    /// Separates a key from its value.
+    pub key_separator: char,
+}
+
+impl Default for RecordFormat {
+    fn default() -> Self {
+        Self {
+            pair_separator: None,
+            key_separator: ':',
+        }
+    }
+}
+
+/// A block of key/value pairs, in the order they appear in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// The (1-based) lines of the problem input making up the record.
+    pub lines: RangeInclusive<usize>,
+    pub fields: Vec<(String, String)>,
+}
+
+impl Record {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Deserializes the record into `T`, parsing values into whatever type the
+    /// corresponding field of `T` has.
+    pub fn deserialize<'de, T: Deserialize<'de>>(&'de self) -> Result<T> {
+        let pairs = self
+            .fields
+            .iter()
+            .map(|(k, v)| (k.as_str(), Value(v.as_str())));
+
+        T::deserialize(MapDeserializer::<_, Error>::new(pairs))
+            .map_err(|e| anyhow!("lines {}-{}: {}", self.lines.start(), self.lines.end(), e))
+    }
+}
+
+impl Fields for Record {
+    fn field(&self, name: &str) -> Option<&str> {
+        self.get(name)
+    }
+}
+
+/// Helper struct for parsing problem inputs which consist of blank line separated
+/// blocks of `key:value` pairs.
+///
+/// # Example usage
+/// let records: Vec<Record> = lines.parse::<Records>().unwrap();
+pub struct Records {
+    t: Vec<Record>,
+}
+
+impl Records {
+    pub fn unwrap(self) -> Vec<Record> {
+        self.t
+    }
+
+    pub fn parse_with(lines: &ProblemInput, format: RecordFormat) -> Result<Self> {
+        let mut records = Vec::new();
+        let mut start = 0;
+
+        // A trailing sentinel closes the final block.
+        for (i, line) in lines
+            .lines
+            .iter()
+            .map(String::as_str)
+            .chain(Some(""))
+            .enumerate()
+        {
+            if !line.is_empty() {
+                continue;
+            }
+
+            if i > start {
+                let mut fields = Vec::new();
+
+                for (line_no, line) in lines.lines[start..i].iter().enumerate() {
+                    let pairs: Vec<&str> = match format.pair_separator {
+                        Some(sep) => line.split(sep).map(str::trim).collect(),
+                        None => line.split_ascii_whitespace().collect(),
+                    };
+
+                    for pair in pairs.into_iter().filter(|p| !p.is_empty()) {
+                        let split = pair.find(format.key_separator).ok_or_else(|| {
+                            anyhow!("line {}: no key in {}", start + line_no + 1, pair)
+                        })?;
+                        let value = &pair[split + format.key_separator.len_utf8()..];
+                        fields.push((pair[..split].to_string(), value.to_string()));
+                    }
+                }
+
+                records.push(Record {
+                    lines: start + 1..=i,
+                    fields,
+                });
+            }
+            start = i + 1;
+        }
+
+        Ok(Self { t: records })
+    }
+}
+
+impl FromProblemInput for Records {
+    fn from(lines: &ProblemInput) -> Self {
+        Self::parse_with(lines, RecordFormat::default()).expect("failed to parse records")
+    }
+}
+
+/// A value in a record, parsed into whichever type is requested of it.
+struct Value<'a>(&'a str);
+
+impl<'de> IntoDeserializer<'de, Error> for Value<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                let parsed = self.0.parse().map_err(|_| {
+                    de::Error::invalid_value(de::Unexpected::Str(self.0), &visitor)
+                })?;
+                visitor.$visit(parsed)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Value<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        BorrowedStrDeserializer::new(self.0).deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum EyeColour {
+        Amb,
+        Blu,
+        Brn,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct TypedRecord {
+        byr: u32,
+        ecl: EyeColour,
+        cid: Option<i64>,
+        hgt: Option<String>,
+    }
+
+    #[test]
+    fn test_typed_records() {
+        let input = ProblemInput::from(vec![
+            "byr=1937, ecl=blu",
+            "cid=-147",
+            "",
+            "ecl=amb, byr=2002",
+        ]);
+        let format = RecordFormat {
+            pair_separator: Some(','),
+            key_separator: '=',
+        };
+        let records = Records::parse_with(&input, format).unwrap().unwrap();
+
+        assert_eq!(records[1].lines, 4..=4);
+        assert_eq!(
+            records[0].deserialize::<TypedRecord>().unwrap(),
+            TypedRecord {
+                byr: 1937,
+                ecl: EyeColour::Blu,
+                cid: Some(-147),
+                hgt: None
+            }
+        );
+        assert_eq!(records[1].deserialize::<TypedRecord>().unwrap().cid, None);
+        assert!(Records::parse_with(&input, RecordFormat::default()).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt::{self, Display, Formatter};
+use std::ops::RangeInclusive;
+
+/// A record whose fields can be looked up by name.
+pub trait Fields {
+    fn field(&self, name: &str) -> Option<&str>;
+}
+
+/// A check applied to the value of a single field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    /// Four digits, within the given range.
+    Year(RangeInclusive<i32>),
+    /// A number followed by `cm` or `in`, within the range for that unit.
+    Height {
+        cm: RangeInclusive<i32>,
+        inches: RangeInclusive<i32>,
+    },
+    /// A `#` followed by exactly six characters 0-9 or a-f.
+    HexColour,
+    /// Exactly one of the given values.
+    OneOf(Vec<String>),
+    /// Exactly this many digits, including leading zeroes.
+    Digits(usize),
+}
+
+pub fn year(range: RangeInclusive<i32>) -> Rule {
+    Rule::Year(range)
+}
+
+pub fn height(cm: RangeInclusive<i32>, inches: RangeInclusive<i32>) -> Rule {
+    Rule::Height { cm, inches }
+}
+
+pub fn hex_colour() -> Rule {
+    Rule::HexColour
+}
+
+pub fn one_of(values: &[&str]) -> Rule {
+    Rule::OneOf(values.iter().map(|v| v.to_string()).collect())
+}
+
+pub fn digits(count: usize) -> Rule {
+    Rule::Digits(count)
+}
+
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())
+}
+
+fn check_range(value: &str, range: &RangeInclusive<i32>, unit: &str) -> Result<(), String> {
+    match value.parse::<i32>() {
+        Ok(x) if is_digits(value) && range.contains(&x) => Ok(()),
+        Ok(_) if is_digits(value) => Err(format!(
+            "{}{} is not in {}..={}",
+            value,
+            unit,
+            range.start(),
+            range.end()
+        )),
+        _ => Err(format!("{} is not a number", value)),
+    }
+}
+
+impl Rule {
+    /// Checks `value` against this rule, returning the reason it fails if it does.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        match self {
+            Rule::Year(range) => {
+                if value.len() != 4 || !is_digits(value) {
+                    return Err(format!("{} is not a four digit year", value));
+                }
+                check_range(value, range, "")
+            }
+            Rule::Height { cm, inches } => {
+                if let Some(x) = value.strip_suffix("cm") {
+                    check_range(x, cm, "cm")
+                } else if let Some(x) = value.strip_suffix("in") {
+                    check_range(x, inches, "in")
+                } else {
+                    Err(format!("{} does not end in cm or in", value))
+                }
+            }
+            Rule::HexColour => match value.strip_prefix('#') {
+                Some(hex)
+                    if hex.len() == 6
+                        && hex
+                            .chars()
+                            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)) =>
+                {
+                    Ok(())
+                }
+                _ => Err(format!("{} is not a hex colour", value)),
+            },
+            Rule::OneOf(values) => {
+                if values.iter().any(|v| v == value) {
+                    Ok(())
+                } else {
+                    Err(format!("{} is not one of {}", value, values.join(", ")))
+                }
+            }
+            Rule::Digits(count) => {
+                if value.len() == *count && is_digits(value) {
+                    Ok(())
+                } else {
+                    Err(format!("{} is not a {} digit number", value, count))
+                }
+            }
+        }
+    }
+}
+
+/// Why a field failed validation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Reason {
+    Missing,
+    Invalid(String),
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::Missing => write!(f, "missing"),
+            Reason::Invalid(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// A field which failed validation, and why.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Failure {
+    pub field: String,
+    pub reason: Reason,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.reason)
+    }
+}
+
+/// A set of rules, each attached to a named field.
+#[derive(Debug, Clone, Default)]
+pub struct Validator {
+    rules: Vec<(String, Rule)>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    pub fn field(mut self, name: &str, rule: Rule) -> Self {
+        self.rules.push((name.to_string(), rule));
+        self
+    }
+
+    /// Checks every rule against `record`, returning all of the failures.
+    pub fn validate<R: Fields + ?Sized>(&self, record: &R) -> Vec<Failure> {
+        self.rules
+            .iter()
+            .filter_map(|(name, rule)| {
+                let reason = match record.field(name) {
+                    Some(value) => Reason::Invalid(rule.check(value).err()?),
+                    None => Reason::Missing,
+                };
+
+                Some(Failure {
+                    field: name.clone(),
+                    reason,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    impl Fields for HashMap<&str, &str> {
+        fn field(&self, name: &str) -> Option<&str> {
+            self.get(name).copied()
+        }
+    }
+
+    #[test]
+    fn test_rules() {
+        let failure = |rule: Rule, value| rule.check(value).unwrap_err();
+
+        assert_eq!(year(1920..=2002).check("2002"), Ok(()));
+        assert_eq!(
+            failure(year(1920..=2002), "2003"),
+            "2003 is not in 1920..=2002"
+        );
+        assert_eq!(
+            failure(year(1920..=2002), "+999"),
+            "+999 is not a four digit year"
+        );
+
+        let height = height(150..=193, 59..=76);
+        assert_eq!(height.check("60in"), Ok(()));
+        assert_eq!(height.check("190cm"), Ok(()));
+        assert_eq!(failure(height.clone(), "190in"), "190in is not in 59..=76");
+        assert_eq!(failure(height.clone(), "-5cm"), "-5 is not a number");
+        assert_eq!(failure(height, "190"), "190 does not end in cm or in");
+
+        assert_eq!(hex_colour().check("#123abc"), Ok(()));
+        assert_eq!(
+            failure(hex_colour(), "#123abz"),
+            "#123abz is not a hex colour"
+        );
+        assert_eq!(
+            failure(hex_colour(), "#123ABC"),
+            "#123ABC is not a hex colour"
+        );
+
+        assert_eq!(one_of(&["amb", "blu"]).check("blu"), Ok(()));
+        assert_eq!(
+            failure(one_of(&["amb", "blu"]), "wat"),
+            "wat is not one of amb, blu"
+        );
+
+        assert_eq!(digits(9).check("000000001"), Ok(()));
+        assert_eq!(
+            failure(digits(9), "0123456789"),
+            "0123456789 is not a 9 digit number"
+        );
+    }
+
+    #[test]
+    fn test_validator() {
+        let validator = Validator::new()
+            .field("byr", year(1920..=2002))
+            .field("ecl", one_of(&["amb", "blu"]))
+            .field("pid", digits(9));
+
+        let record: HashMap<_, _> = vec![("byr", "1980"), ("ecl", "wat"), ("cid", "1")]
+            .into_iter()
+            .collect();
+        let failures = validator.validate(&record);
+
+        assert_eq!(
+            failures,
+            vec![
+                Failure {
+                    field: "ecl".to_string(),
+                    reason: Reason::Invalid("wat is not one of amb, blu".to_string()),
+                },
+                Failure {
+                    field: "pid".to_string(),
+                    reason: Reason::Missing,
+                },
+            ]
+        );
+        assert_eq!(failures[1].to_string(), "pid: missing");
+    }
+}